@@ -1,467 +1,3469 @@
-use core::iter;
 use proc_macro::TokenStream;
-use proc_macro2::Span;
+use proc_macro2::{Span, TokenStream as TokenStream2};
 use proc_macro_crate::{crate_name, FoundCrate};
-use quote::{quote, quote_spanned};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use regex_syntax::{
+    hir::{Class, ClassBytes, ClassBytesRange, Dot, Hir, HirKind, Repetition},
+    utf8::Utf8Sequences,
+    ParserBuilder,
+};
 use replace_with::replace_with;
+use std::collections::HashMap;
+use std::hash::Hash;
 use syn::{
-    parse_macro_input, parse_quote, parse_quote_spanned, parse_str, spanned::Spanned, Arm, Expr,
-    ExprArray, ExprMatch, Ident, Lit, LitByte, Pat, PatIdent,
+    ext::IdentExt,
+    parse_macro_input, parse_quote, parse_quote_spanned, parse_str,
+    spanned::Spanned,
+    visit_mut::{self, VisitMut},
+    Arm, Attribute, Data, DeriveInput, Expr, ExprMatch, Fields, Ident, Item, Lit, LitByte,
+    LitByteStr, LitStr, Meta, Pat, PatIdent, PatLit, Path, RangeLimits, Token, Variant,
 };
 
-// return the body of the arm of `m` with the given byte as its pattern, if it exists
-fn find_arm(m: &mut ExprMatch, byte: u8) -> Option<&mut Expr> {
-    for arm in m.arms.iter_mut() {
-        // these ugly nested if statements are just to get at
-        // the literal byte (e.g. the 1 in Some(Ok(b'\x01')))
-        if let Pat::TupleStruct(expr) = &arm.pat {
-            if expr.path == parse_quote!(::core::option::Option::Some) && expr.pat.elems.len() == 1
+// a set of bytes, any of which lead to the same node of the trie
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+struct ByteSet([u64; 4]);
+
+impl ByteSet {
+    fn range(lo: u8, hi: u8) -> Self {
+        let mut set = Self::default();
+        for b in lo..=hi {
+            set.insert(b);
+        }
+        set
+    }
+
+    fn insert(&mut self, byte: u8) {
+        self.0[byte as usize / 64] |= 1 << (byte % 64);
+    }
+
+    fn contains(&self, byte: u8) -> bool {
+        self.0[byte as usize / 64] & (1 << (byte % 64)) != 0
+    }
+
+    fn union(&self, other: &Self) -> Self {
+        Self(core::array::from_fn(|i| self.0[i] | other.0[i]))
+    }
+
+    fn intersection(&self, other: &Self) -> Self {
+        Self(core::array::from_fn(|i| self.0[i] & other.0[i]))
+    }
+
+    fn difference(&self, other: &Self) -> Self {
+        Self(core::array::from_fn(|i| self.0[i] & !other.0[i]))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.iter().all(|&x| x == 0)
+    }
+
+    fn len(&self) -> usize {
+        self.0.iter().map(|x| x.count_ones() as usize).sum()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..=u8::MAX).filter(|&b| self.contains(b))
+    }
+
+    // the set plus the other case of each ASCII letter in it
+    fn ignore_ascii_case(&self) -> Self {
+        let mut set = *self;
+        for b in self.iter() {
+            set.insert(b.to_ascii_lowercase());
+            set.insert(b.to_ascii_uppercase());
+        }
+        set
+    }
+
+    // the bytes in the set as a list of inclusive ranges, in order
+    fn ranges(&self) -> Vec<(u8, u8)> {
+        let mut ranges: Vec<(u8, u8)> = Vec::new();
+        for b in self.iter() {
+            match ranges.last_mut() {
+                Some((_, hi)) if *hi as usize + 1 == b as usize => *hi = b,
+                _ => ranges.push((b, b)),
+            }
+        }
+        ranges
+    }
+}
+
+impl From<u8> for ByteSet {
+    fn from(byte: u8) -> Self {
+        let mut set = Self::default();
+        set.insert(byte);
+        set
+    }
+}
+
+// an arm whose pattern matched, and which of the cases in the pattern matched
+// (wild arms have no cases). sorts in the order the arms should be tried
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Accept {
+    arm: usize,
+    case: Option<usize>,
+}
+
+// the arms in `accepts`, in order and with only the first case for each arm
+fn first_cases(accepts: &[Accept]) -> Vec<Accept> {
+    let mut firsts: Vec<Accept> = Vec::new();
+    for &accept in accepts {
+        if firsts.iter().all(|first| first.arm != accept.arm) {
+            firsts.push(accept);
+        }
+    }
+    firsts
+}
+
+// one state of the NFA every case is compiled into before we build the DFA
+#[derive(Default)]
+struct NfaState {
+    // the states we can move to without reading anything
+    eps: Vec<usize>,
+    // the states we can move to by reading one of a set of bytes, along with
+    // the span of the pattern that needs them (for the byte literals)
+    edges: Vec<(ByteSet, Span, usize)>,
+    // the arm that matches if the input ends in this state, and whether it
+    // matches no matter what comes after (for Prefix and wild arms)
+    accept: Option<(Accept, bool)>,
+}
+
+struct Nfa {
+    states: Vec<NfaState>,
+    // whether to compile patterns from end to start (for Suffix)
+    reverse: bool,
+    // whether sticky states only match where they are, for longest-match
+    // (the DFA keeps track of the last one that matched instead)
+    longest: bool,
+}
+
+impl Nfa {
+    fn add(&mut self) -> usize {
+        self.states.push(NfaState::default());
+        self.states.len() - 1
+    }
+
+    fn edge(&mut self, from: usize, bytes: ByteSet, span: Span) -> usize {
+        let to = self.add();
+        self.states[from].edges.push((bytes, span, to));
+        to
+    }
+
+    // a state the input can end in for `accept` to match. for a `sticky` one,
+    // it still matches after reading any number of other bytes
+    fn accept(&mut self, from: usize, accept: Accept, sticky: bool) {
+        let to = self.add();
+        self.states[from].eps.push(to);
+        self.states[to].accept = Some((accept, sticky));
+        if sticky && !self.longest {
+            self.states[to]
+                .edges
+                .push((ByteSet::range(0, u8::MAX), Span::call_site(), to));
+        }
+    }
+
+    // add the states to match `hir` starting at `from`, returning the state
+    // the match ends in
+    fn compile(&mut self, hir: &Hir, from: usize, span: Span) -> usize {
+        match hir.kind() {
+            HirKind::Empty => from,
+            HirKind::Look(_) => unreachable!("parse_arm rejects regexes with anchors"),
+            HirKind::Literal(lit) => {
+                let mut bytes = lit.0.to_vec();
+                if self.reverse {
+                    bytes.reverse();
+                }
+                bytes
+                    .into_iter()
+                    .fold(from, |state, b| self.edge(state, b.into(), span))
+            }
+            HirKind::Class(Class::Bytes(class)) => {
+                let mut set = ByteSet::default();
+                for range in class.iter() {
+                    set = set.union(&ByteSet::range(range.start(), range.end()));
+                }
+                self.edge(from, set, span)
+            }
+            HirKind::Class(Class::Unicode(class)) => {
+                // each range of chars is a few sequences of UTF-8 byte ranges
+                let to = self.add();
+                for range in class.iter() {
+                    for mut seq in Utf8Sequences::new(range.start(), range.end()) {
+                        if self.reverse {
+                            seq.reverse();
+                        }
+                        let end = seq.as_slice().iter().fold(from, |state, range| {
+                            self.edge(state, ByteSet::range(range.start, range.end), span)
+                        });
+                        self.states[end].eps.push(to);
+                    }
+                }
+                to
+            }
+            HirKind::Repetition(rep) => {
+                let mut state = from;
+                for _ in 0..rep.min {
+                    state = self.compile(&rep.sub, state, span);
+                }
+
+                match rep.max {
+                    // a loop back to a state of its own, so that whatever
+                    // comes next doesn't end up inside the loop
+                    None => {
+                        let head = self.add();
+                        self.states[state].eps.push(head);
+                        let end = self.compile(&rep.sub, head, span);
+                        self.states[end].eps.push(head);
+                        head
+                    }
+                    Some(max) => {
+                        for _ in rep.min..max {
+                            let end = self.compile(&rep.sub, state, span);
+                            let skip = self.add();
+                            self.states[state].eps.push(skip);
+                            self.states[end].eps.push(skip);
+                            state = skip;
+                        }
+                        state
+                    }
+                }
+            }
+            HirKind::Capture(capture) => self.compile(&capture.sub, from, span),
+            HirKind::Concat(hirs) => {
+                let mut state = from;
+                let mut hirs: Vec<_> = hirs.iter().collect();
+                if self.reverse {
+                    hirs.reverse();
+                }
+                for hir in hirs {
+                    state = self.compile(hir, state, span);
+                }
+                state
+            }
+            HirKind::Alternation(hirs) => {
+                let to = self.add();
+                for hir in hirs {
+                    let end = self.compile(hir, from, span);
+                    self.states[end].eps.push(to);
+                }
+                to
+            }
+        }
+    }
+
+    // `states` plus every state reachable from them without reading anything
+    fn closure(&self, mut states: Vec<usize>) -> Vec<usize> {
+        let mut i = 0;
+        while i < states.len() {
+            for &next in &self.states[states[i]].eps {
+                if !states.contains(&next) {
+                    states.push(next);
+                }
+            }
+            i += 1;
+        }
+
+        states.sort_unstable();
+        states
+    }
+
+    // split up all bytes into sets that every edge either takes all or none
+    // of, so the DFA can be built a set at a time instead of a byte at a time
+    fn classes(&self) -> Vec<ByteSet> {
+        let mut sets: Vec<ByteSet> = self
+            .states
+            .iter()
+            .flat_map(|state| state.edges.iter().map(|&(bytes, _, _)| bytes))
+            .collect();
+        sets.sort_unstable_by_key(|set| set.0);
+        sets.dedup();
+
+        let mut classes = vec![ByteSet::range(0, u8::MAX)];
+        for set in sets {
+            classes = classes
+                .into_iter()
+                .flat_map(|class| [class.intersection(&set), class.difference(&set)])
+                .filter(|class| !class.is_empty())
+                .collect();
+        }
+        classes
+    }
+}
+
+// the most states the DFA for one lighter! can have, so that a regex like
+// (a|b)*a(a|b){20} is an error rather than a compiler that never finishes
+const MAX_STATES: usize = 10_000;
+
+// one state of the DFA built out of the patterns in the match statement. each
+// state that can't be resolved without reading another byte becomes a
+// `match __lighter_internal_iter.next()` in the generated code. for literal
+// patterns, the states form a trie
+struct State {
+    // the next state for each (disjoint) set of bytes that could come next,
+    // along with the span of the first pattern to need it
+    edges: Vec<(ByteSet, Span, usize)>,
+    // the arms that match if the input ends in this state
+    end: Vec<Accept>,
+    // the arms that match in this state no matter what comes after
+    // (for longest-match, the ones that matched last)
+    sticky: Vec<Accept>,
+    // the arms that could match in this state or any state after it
+    live: Vec<usize>,
+    // the first arm that hasn't already matched but still could if we read
+    // another byte (or find that the input has ended). for longest-match,
+    // any arm that could still match more of the input
+    below: Option<usize>,
+}
+
+impl State {
+    fn is_sticky(&self, arm: usize) -> bool {
+        self.sticky.iter().any(|accept| accept.arm == arm)
+    }
+
+    fn first_below(&self) -> Option<usize> {
+        self.live
+            .iter()
+            .copied()
+            .filter(|&arm| !self.is_sticky(arm))
+            .min()
+    }
+}
+
+// merge the states that match the same arms for the same input, e.g. the
+// states before and after the first loop of a regex like [a-z]+ (the
+// first state stays first)
+fn minimize(states: Vec<State>, classes: &[ByteSet]) -> Vec<State> {
+    // start with the states split up by which arms they accept, then keep
+    // splitting them up by which groups their edges lead to
+    #[allow(clippy::type_complexity)]
+    let mut keys: HashMap<(&[Accept], &[Accept], &[usize], Option<usize>), usize> = HashMap::new();
+    let mut group: Vec<usize> = states
+        .iter()
+        .map(|state| {
+            let len = keys.len();
+            *keys
+                .entry((&state.end, &state.sticky, &state.live, state.below))
+                .or_insert(len)
+        })
+        .collect();
+
+    let mut groups = keys.len();
+    loop {
+        let mut keys: HashMap<(usize, Vec<Option<usize>>), usize> = HashMap::new();
+        let next: Vec<usize> = states
+            .iter()
+            .enumerate()
+            .map(|(i, state)| {
+                let targets: Vec<Option<usize>> = classes
+                    .iter()
+                    .map(|class| {
+                        let b = class.iter().next().unwrap();
+                        state
+                            .edges
+                            .iter()
+                            .find(|(bytes, _, _)| bytes.contains(b))
+                            .map(|&(_, _, to)| group[to])
+                    })
+                    .collect();
+
+                let len = keys.len();
+                *keys.entry((group[i], targets)).or_insert(len)
+            })
+            .collect();
+
+        group = next;
+        if keys.len() == groups {
+            break;
+        }
+        groups = keys.len();
+    }
+
+    // the first state in each group stands in for the rest
+    let mut merged: Vec<State> = Vec::new();
+    let mut firsts: Vec<usize> = Vec::new();
+    for (i, state) in states.into_iter().enumerate() {
+        if group[i] == firsts.len() {
+            firsts.push(i);
+            merged.push(state);
+        }
+    }
+
+    for state in &mut merged {
+        let mut edges: Vec<(ByteSet, Span, usize)> = Vec::new();
+        for &(bytes, span, to) in &state.edges {
+            match edges
+                .iter_mut()
+                .find(|(_, _, edge_to)| *edge_to == group[to])
             {
-                if let Some(Pat::TupleStruct(expr)) = expr.pat.elems.first() {
-                    if expr.path == parse_quote!(::core::result::Result::Ok)
-                        && expr.pat.elems.len() == 1
-                    {
-                        if let Some(Pat::Lit(expr)) = expr.pat.elems.first() {
-                            if let Expr::Lit(expr) = expr.expr.as_ref() {
-                                if let Lit::Byte(b) = &expr.lit {
-                                    if b.value() == byte {
-                                        return Some(&mut arm.body);
-                                    }
-                                }
+                Some((edge_bytes, _, _)) => *edge_bytes = edge_bytes.union(&bytes),
+                None => edges.push((bytes, span, group[to])),
+            }
+        }
+        state.edges = edges;
+    }
+
+    merged
+}
+
+// for longest-match, which arms a state runs depends on the states on the way
+// there where a Prefix arm matched, so split each state up by those arms,
+// longest match first: if the guards of the arms that matched the most input
+// fail, the ones that matched less still get a try
+fn remember_last(states: Vec<State>) -> syn::Result<Vec<State>> {
+    // wild arms still run if nothing longer matched
+    let wild: Vec<Accept> = states[0]
+        .sticky
+        .iter()
+        .copied()
+        .filter(|accept| accept.case.is_none())
+        .collect();
+
+    let mut keys = vec![(0, Vec::new())];
+    let mut ids: HashMap<(usize, Vec<Accept>), usize> = HashMap::from([(keys[0].clone(), 0)]);
+    let mut split: Vec<State> = Vec::new();
+    while split.len() < keys.len() {
+        if keys.len() > MAX_STATES {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                format!(
+                    "the patterns in this lighter! need more than {} states to match",
+                    MAX_STATES
+                ),
+            ));
+        }
+
+        let (i, stack) = keys[split.len()].clone();
+        let node = &states[i];
+        let sticky = first_cases(&[&stack[..], &wild].concat());
+
+        // matching the whole input beats any shorter prefix of it, and ties
+        // with the prefixes that matched right here
+        let whole = node
+            .end
+            .iter()
+            .copied()
+            .filter(|accept| accept.case.is_some());
+        let end = first_cases(&whole.chain(sticky.iter().copied()).collect::<Vec<_>>());
+
+        let edges = node
+            .edges
+            .iter()
+            .map(|&(bytes, span, to)| {
+                // an arm that matches again here matched more input than it
+                // did before, so it moves to the top
+                let here: Vec<Accept> = states[to]
+                    .sticky
+                    .iter()
+                    .copied()
+                    .filter(|accept| accept.case.is_some())
+                    .collect();
+                let stack: Vec<Accept> = here
+                    .iter()
+                    .chain(
+                        stack
+                            .iter()
+                            .filter(|below| here.iter().all(|accept| accept.arm != below.arm)),
+                    )
+                    .copied()
+                    .collect();
+                let to = *ids.entry((to, stack)).or_insert_with_key(|key| {
+                    keys.push(key.clone());
+                    keys.len() - 1
+                });
+                (bytes, span, to)
+            })
+            .collect();
+
+        split.push(State {
+            edges,
+            end,
+            sticky,
+            live: node.live.clone(),
+            below: node.below,
+        });
+    }
+
+    Ok(split)
+}
+
+// turn the NFA starting at `start` into a DFA starting at state 0
+fn build_dfa(nfa: &Nfa, start: usize) -> syn::Result<Vec<State>> {
+    let classes = nfa.classes();
+    let mut keys = vec![nfa.closure(vec![start])];
+    let mut ids: HashMap<Vec<usize>, usize> = HashMap::from([(keys[0].clone(), 0)]);
+    let mut states: Vec<State> = Vec::new();
+    while states.len() < keys.len() {
+        if keys.len() > MAX_STATES {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                format!(
+                    "the patterns in this lighter! need more than {} states to match",
+                    MAX_STATES
+                ),
+            ));
+        }
+
+        let key = keys[states.len()].clone();
+        let mut state = State {
+            edges: Vec::new(),
+            end: Vec::new(),
+            sticky: Vec::new(),
+            live: Vec::new(),
+            below: None,
+        };
+        for &i in &key {
+            if let Some((accept, sticky)) = nfa.states[i].accept {
+                state.end.push(accept);
+                if sticky {
+                    state.sticky.push(accept);
+                }
+            }
+        }
+        state.end.sort_unstable();
+        state.sticky.sort_unstable();
+
+        for class in &classes {
+            let b = class.iter().next().unwrap();
+            let mut next = Vec::new();
+            let mut span = None;
+            for &i in &key {
+                for (bytes, edge_span, to) in &nfa.states[i].edges {
+                    if bytes.contains(b) && !next.contains(to) {
+                        next.push(*to);
+                        span.get_or_insert(*edge_span);
+                    }
+                }
+            }
+            if next.is_empty() {
+                continue;
+            }
+
+            let next = nfa.closure(next);
+            let to = *ids.entry(next.clone()).or_insert_with(|| {
+                keys.push(next);
+                keys.len() - 1
+            });
+            match state
+                .edges
+                .iter_mut()
+                .find(|(_, _, edge_to)| *edge_to == to)
+            {
+                Some((bytes, _, _)) => *bytes = bytes.union(class),
+                None => state.edges.push((*class, span.unwrap(), to)),
+            }
+        }
+
+        states.push(state);
+    }
+
+    // an arm is live in a state if it's live in any state after it
+    for state in &mut states {
+        state.live = state.end.iter().map(|accept| accept.arm).collect();
+    }
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for i in 0..states.len() {
+            let mut live = states[i].live.clone();
+            for &(_, _, to) in &states[i].edges {
+                live.extend(&states[to].live);
+            }
+            live.sort_unstable();
+            live.dedup();
+            if live != states[i].live {
+                states[i].live = live;
+                changed = true;
+            }
+        }
+    }
+
+    for i in 0..states.len() {
+        states[i].below = if nfa.longest {
+            // an arm that matches if the input ends here has to wait to see
+            // whether it does, just like one that could match more of it
+            let state = &states[i];
+            let after = state.edges.iter().flat_map(|&(_, _, to)| &states[to].live);
+            let whole = state
+                .end
+                .iter()
+                .filter(|accept| !state.is_sticky(accept.arm))
+                .map(|accept| &accept.arm);
+            after.chain(whole).copied().min()
+        } else {
+            states[i].first_below()
+        };
+    }
+
+    if nfa.longest {
+        states = remember_last(states)?;
+    }
+
+    Ok(minimize(states, &classes))
+}
+
+fn byte_lit(byte: u8, span: Span) -> LitByte {
+    // the format! is a workaround for a bug in LitByte::value where values
+    // created with LitByte::new are not parsed correctly
+    // (TODO: report this bug)
+    let mut b = parse_str::<LitByte>(&format!("b'\\x{:02x}'", byte)).unwrap();
+    b.set_span(span);
+    b
+}
+
+// a pattern matching any byte in `bytes`, e.g. `b'h' | b'H'`
+fn byte_pat(bytes: &ByteSet, span: Span) -> Pat {
+    let ranges = bytes.ranges().into_iter().map(|(lo, hi)| {
+        let (lo_lit, hi_lit) = (byte_lit(lo, span), byte_lit(hi, span));
+        match hi - lo {
+            0 => quote!(#lo_lit),
+            1 => quote!(#lo_lit | #hi_lit),
+            _ => quote!(#lo_lit..=#hi_lit),
+        }
+    });
+
+    parse_quote!(#(#ranges)|*)
+}
+
+// how to generate code for the trie
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+enum Strategy {
+    // a match statement for each node, nested inside each other
+    #[default]
+    Nested,
+    // a static transition table and a loop that runs through it
+    Table,
+    // a match on the length of the input, then a nested match for each
+    // length with only the patterns that could match that many bytes. only
+    // ever picked by hand: patterns of different lengths no longer share the
+    // nodes for their common prefix, so for a big set of keywords this is
+    // more code than Nested and no faster to compile
+    Length,
+}
+
+// which arm wins when the patterns of more than one arm match
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+enum Prefer {
+    // the first one, like in a regular match statement
+    #[default]
+    First,
+    // the one that matched the most input, like a lexer (ties go to the
+    // first one, and `_` and identifier arms match none of it)
+    Longest,
+}
+
+// the settings a #[lighter(...)] attribute on the match statement can change
+#[derive(Default)]
+struct Options {
+    // #[lighter(crate = path)]: where to find lighter, if not under its own name
+    krate: Option<Path>,
+    // #[lighter(case_insensitive)]: treat every pattern as IgnoreAsciiCase(...)
+    case_insensitive: bool,
+    // #[lighter(strategy = "...")]
+    strategy: Strategy,
+    // #[lighter(prefer = "...")]
+    prefer: Prefer,
+}
+
+fn parse_options(attrs: &[Attribute]) -> syn::Result<Options> {
+    let mut opts = Options::default();
+    for attr in attrs {
+        if !attr.path().is_ident("lighter") {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "lighter! only supports #[lighter(...)] attributes on the match expression",
+            ));
+        }
+
+        // a bare #[lighter] just marks the match for #[rewrite]
+        if let Meta::Path(_) = attr.meta {
+            continue;
+        }
+
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("crate") {
+                opts.krate = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("case_insensitive") {
+                opts.case_insensitive = true;
+            } else if meta.path.is_ident("strategy") {
+                let strategy: LitStr = meta.value()?.parse()?;
+                opts.strategy = match strategy.value().as_str() {
+                    "nested" => Strategy::Nested,
+                    "table" => Strategy::Table,
+                    "length" => Strategy::Length,
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            strategy,
+                            "unknown strategy; expected \"nested\", \"table\" or \"length\"",
+                        ))
+                    }
+                };
+            } else if meta.path.is_ident("prefer") {
+                let prefer: LitStr = meta.value()?.parse()?;
+                opts.prefer = match prefer.value().as_str() {
+                    "first" => Prefer::First,
+                    "longest" => Prefer::Longest,
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            prefer,
+                            "unknown preference; expected \"first\" or \"longest\"",
+                        ))
+                    }
+                };
+            } else {
+                return Err(meta.error(
+                    "unknown lighter option; expected `crate`, `case_insensitive`, \
+                     `strategy` or `prefer`",
+                ));
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(opts)
+}
+
+// what generating code for the trie needs to know about the match statement
+#[derive(Clone, Copy)]
+struct Context<'a> {
+    arms: &'a [Arm],
+    cases: &'a [Case],
+    states: &'a [State],
+    // the sets of bytes every edge of the DFA either takes all or none of
+    classes: &'a [ByteSet],
+    opts: &'a Options,
+    // whether the trie holds the patterns from end to start (for Suffix)
+    reverse: bool,
+    // the end of the word whose bytes we're reading one at a time because
+    // reading it all at once failed. getting there gives None, so the code
+    // after it is shared with the fast path, and the arms that run on the way
+    // give Some of what they return
+    word_end: Option<usize>,
+}
+
+// the guard and body to emit for an arm once we know its pattern has matched.
+// identifier patterns bind the bytes read so far, which are `path` followed by
+// __lighter_internal_last_byte if `last_byte` is set (or the other way around
+// if we are reading the input in reverse), as long as we didn't read them in a
+// loop and so know how many there are. `@` patterns bind the text of the
+// case that matched. when the arm has a guard, the binding is made in both the
+// guard and the body so that both can use it
+fn arm_parts(
+    ctx: &Context,
+    accept: Accept,
+    path: Option<&[TokenStream2]>,
+    last_byte: bool,
+) -> (Option<Expr>, Expr) {
+    let arm = &ctx.arms[accept.arm];
+
+    // make a short name for arm.body, because quote! would
+    // expand #arm.body as (#arm).body, not #(arm.body)
+    let body = &arm.body;
+    let guard = arm.guard.as_ref().map(|(_, guard)| guard);
+
+    // parse_arm already rejected `ref` bindings
+    let mut lets = Vec::new();
+    match &arm.pat {
+        Pat::Ident(PatIdent { subpat: None, .. }) if accept.case.is_some() => {}
+        Pat::Ident(PatIdent {
+            ident,
+            subpat: None,
+            ..
+        }) if path.is_none() => {
+            let error = quote_spanned! {ident.span()=>
+                ::core::compile_error!(
+                    "this identifier pattern would have to bind bytes read by a loop in a Regex(...) or Glob(...) pattern, \
+                     which don't fit in an array"
+                )
+            };
+            return (None, parse_quote!(#error));
+        }
+        Pat::Ident(PatIdent {
+            mutability,
+            ident,
+            subpat: None,
+            ..
+        }) => {
+            let path = path.unwrap();
+            let len = path.len() + last_byte as usize;
+            let mut bytes: Vec<_> = path
+                .iter()
+                .cloned()
+                .chain(last_byte.then(|| quote!(__lighter_internal_last_byte)))
+                .collect();
+            if ctx.reverse {
+                bytes.reverse();
+            }
+
+            lets.push((
+                mutability,
+                ident,
+                quote!(: [u8; #len]),
+                quote!([#(#bytes),*]),
+            ));
+        }
+        Pat::Ident(PatIdent {
+            mutability, ident, ..
+        }) => {
+            // parse_arm made sure every case in the arm has text; the binding
+            // is a &str only if they all do, since they must have one type
+            let as_str = ctx
+                .cases
+                .iter()
+                .filter(|case| case.arm == accept.arm)
+                .all(|case| matches!(case.text, Some(Text::Str(_))));
+            let case = &ctx.cases[accept.case.expect("`@` arms only match with a case")];
+
+            match &case.text {
+                Some(Text::Str(s)) if as_str => {
+                    let s = LitStr::new(s, case.span);
+                    lets.push((mutability, ident, quote!(: &'static str), quote!(#s)));
+                }
+                Some(text) => {
+                    let s = LitByteStr::new(text.as_bytes(), case.span);
+                    lets.push((mutability, ident, quote!(: &'static [u8]), quote!(#s)));
+                }
+                None => unreachable!("parse_arm rejects `@` patterns without text"),
+            }
+        }
+        _ => {}
+    }
+
+    let case = accept.case.map(|case| &ctx.cases[case]);
+
+    // the names a Seq(...) binds get the part of the input they matched,
+    // which the iterator either still has or recorded along the way
+    if let Some(case) = case {
+        for (i, step) in case.steps.iter().enumerate() {
+            let (segment, binding, aligned) = match step {
+                Step::Segment(segment, Some(binding), aligned) => (segment, binding, *aligned),
+                _ => continue,
+            };
+            let walk = walk(&case.steps[..i]);
+            let skip = segment.skip();
+            // if the input is a str and the part could start or end partway
+            // through a char, the error about it should point at the binding
+            let span = Span::call_site().located_at(binding.ident.span());
+            lets.push((
+                &binding.mutability,
+                &binding.ident,
+                quote!(),
+                quote_spanned! {span=> {
+                    #walk
+                    let __lighter_internal_start = __lighter_internal_pos;
+                    #skip
+                    Capture::<#aligned>::capture(
+                        &__lighter_internal_iter,
+                        __lighter_internal_start,
+                        __lighter_internal_pos,
+                    )
+                }},
+            ));
+        }
+    }
+
+    // Prefix(..., rest) gets whatever of the input the prefix didn't cover,
+    // which only the iterator knows how to find
+    if let Some((case, rest)) = case.and_then(|case| Some((case, case.rest.as_ref()?))) {
+        let len = match case.len {
+            Some(len) => quote!(#len),
+            None => {
+                assert!(
+                    case.measured,
+                    "parse_arm rejects binding the rest after a pattern without a known length"
+                );
+                let walk = walk(&case.steps);
+                quote!({
+                    #walk
+                    __lighter_internal_pos
+                })
+            }
+        };
+        // if the input is a str and the rest could start partway through a
+        // char, the error about it should point at the binding
+        let back = case.anchor == Anchor::End;
+        let aligned = case.aligned;
+        let span = Span::call_site().located_at(rest.ident.span());
+        lets.push((
+            &rest.mutability,
+            &rest.ident,
+            quote!(),
+            quote_spanned! {span=> {
+                let __lighter_internal_len = #len;
+                Remainder::<#aligned>::remainder(&mut __lighter_internal_iter, __lighter_internal_len, #back)
+            }},
+        ));
+    }
+
+    if lets.is_empty() {
+        return (
+            guard.map(|guard| (**guard).clone()),
+            parse_quote!(::core::result::Result::Ok(#body)),
+        );
+    }
+
+    // a normal match statement doesn't warn about a binding that
+    // only the guard or only the body uses, so we shouldn't either
+    let allow = guard.map(|_| quote!(#[allow(unused_variables)]));
+    let guard_lets = lets
+        .iter()
+        .map(|(_, ident, ty, value)| quote!(#allow let #ident #ty = #value;));
+    let body_lets = lets
+        .iter()
+        .map(|(mutability, ident, ty, value)| quote!(#allow let #mutability #ident #ty = #value;));
+
+    (
+        guard.map(|guard| {
+            parse_quote! {
+                {
+                    #(#guard_lets)*
+                    #guard
+                }
+            }
+        }),
+        parse_quote! {
+            {
+                #(#body_lets)*
+                ::core::result::Result::Ok(#body)
+            }
+        },
+    )
+}
+
+// append an arm with the pattern `pat` to `m` for each of the arms in
+// `candidates` (in order) until one is unguarded and thus always runs. this is
+// how the arms that already matched or the wild arms end up running when the
+// next byte rules out every other arm
+fn insert_wild(
+    ctx: &Context,
+    m: &mut ExprMatch,
+    pat: &Pat,
+    candidates: impl IntoIterator<Item = Accept>,
+    path: Option<&[TokenStream2]>,
+    last_byte: bool,
+) {
+    for accept in candidates {
+        let (guard, mut body) = arm_parts(ctx, accept, path, last_byte);
+        if ctx.word_end.is_some() {
+            body = parse_quote!(::core::option::Option::Some(#body));
+        }
+        let attrs = &ctx.arms[accept.arm].attrs;
+        let guard = guard.map(|guard| quote!(if #guard));
+
+        // we are stuck between a rock and a hard place: if the arm
+        // is something like _ => Ok(continue), rustc will throw an
+        // "unreachable call" warning because the Ok will never be
+        // constructed. on the other hand, if we disable this
+        // warning for the entire match arm, real unreachable code
+        // warnings for the original match arm body are ignored. we
+        // choose to ignore all unreachable code warnings because
+        // in my experience so far that has been more ergononic for
+        // the user versus printing many spurious errors.
+        // TODO: when attributes can be added to expressions stably
+        // just make the body #[allow(unreachable_code)] Ok(#expr)
+        // https://github.com/rust-lang/rust/issues/15701
+        m.arms.push(parse_quote! {
+            #(#attrs)*
+            #[allow(unreachable_code)]
+            #pat #guard => #body,
+        });
+
+        if guard.is_none() {
+            break;
+        }
+    }
+}
+
+// the method to get the next byte of the input from __lighter_internal_iter,
+// which is next_back() if the trie holds the patterns from end to start
+fn next_byte(reverse: bool, span: Span) -> Ident {
+    let name = if reverse { "next_back" } else { "next" };
+    Ident::new(name, span)
+}
+
+// the arms that already matched in `state` and can run before we read
+// another byte, in order
+fn runs_now(ctx: &Context, state: usize, tried: &[usize]) -> Vec<Accept> {
+    let node = &ctx.states[state];
+    let longest = ctx.opts.prefer == Prefer::Longest;
+    let mut matched: Vec<Accept> = first_cases(&node.sticky)
+        .into_iter()
+        .filter(|accept| !tried.contains(&accept.arm))
+        .collect();
+
+    // if no earlier arm can match any more, an arm that already matched can
+    // run before we read another byte. like a regular match statement, when
+    // its guard fails we go on to try the next arm. wild arms wait until no
+    // arm at all can match, so an identifier pattern binds all the bytes
+    // that any arm needed to look at. for longest-match, every arm waits
+    // until no arm could match more
+    let below = node.below;
+    let pending = matched
+        .iter()
+        .position(|accept| {
+            below.is_some_and(|below| longest || below < accept.arm || accept.case.is_none())
+        })
+        .unwrap_or(matched.len());
+    matched.truncate(pending);
+    matched
+}
+
+// the most bytes we read at once, as a u64
+const MAX_WORD: usize = 8;
+
+// the bytes along the run of states starting at `state`, where each one can
+// only go on to the next by reading one certain byte and has no arm to run
+// before it does, and the state at its end. slices and strs can read all of
+// them at once, and only go through the states one at a time if that fails
+fn find_word(ctx: &Context, mut state: usize, tried: &[usize]) -> (Vec<u8>, usize) {
+    let mut word = Vec::new();
+    while word.len() < MAX_WORD {
+        let mut edges = real_edges(ctx, state);
+        let (bytes, _, to) = match (edges.next(), edges.next()) {
+            (Some(edge), None) if edge.0.len() == 1 => edge,
+            _ => break,
+        };
+        word.push(bytes.iter().next().unwrap());
+        state = *to;
+
+        // the state at the end has to read another byte right away too
+        if !runs_now(ctx, state, tried).is_empty() || !find_loop(ctx, state, tried).is_empty() {
+            break;
+        }
+    }
+    (word, state)
+}
+
+// generate the code for the part of the DFA starting at `state`. `path` holds
+// an expression for each byte read to get here, unless we went through a loop
+// and don't know them. `tried` holds the arms whose guards already failed
+fn make_match(
+    ctx: &Context,
+    state: usize,
+    mut path: Option<&mut Vec<TokenStream2>>,
+    tried: &[usize],
+) -> Expr {
+    if ctx.word_end == Some(state) {
+        return parse_quote!(::core::option::Option::None);
+    }
+
+    let matched = runs_now(ctx, state, tried);
+    let now = &matched[..];
+
+    let unguarded = now
+        .iter()
+        .position(|accept| ctx.arms[accept.arm].guard.is_none());
+    let (now, mut expr) = match unguarded {
+        Some(i) => (
+            &now[..i],
+            arm_parts(ctx, now[i], path.as_deref().map(|path| &path[..]), false).1,
+        ),
+        None => {
+            // we have to read another byte to see which arm matches
+            let tried: Vec<usize> = tried
+                .iter()
+                .copied()
+                .chain(now.iter().map(|accept| accept.arm))
+                .collect();
+            let looped = find_loop(ctx, state, &tried);
+            let (word, end) = match ctx.word_end {
+                None if looped.is_empty() => find_word(ctx, state, &tried),
+                _ => (Vec::new(), state),
+            };
+            let expr = if looped.is_empty() {
+                let next = next_byte(ctx.reverse, Span::call_site());
+                let next = parse_quote!(__lighter_internal_iter.#next());
+                let slow = Context {
+                    word_end: match word.len() {
+                        0 | 1 => ctx.word_end,
+                        _ => Some(end),
+                    },
+                    ..*ctx
+                };
+                let slow = Expr::Match(read_match(
+                    &slow,
+                    state,
+                    next,
+                    path.as_deref_mut(),
+                    &tried,
+                    &HashMap::new(),
+                ));
+
+                if word.len() > 1 {
+                    let lits: Vec<_> = word
+                        .iter()
+                        .map(|&b| byte_lit(b, Span::call_site()))
+                        .collect();
+                    let fast = match path.as_deref_mut() {
+                        Some(path) => {
+                            let len = path.len();
+                            path.extend(lits.iter().map(|b| quote!(#b)));
+                            let expr = make_match(ctx, end, Some(&mut *path), &tried);
+                            path.truncate(len);
+                            expr
+                        }
+                        None => make_match(ctx, end, None, &tried),
+                    };
+
+                    // the word is in the order we read it, which is
+                    // backwards from the input for a Suffix
+                    let mut lits = lits;
+                    if ctx.reverse {
+                        lits.reverse();
+                    }
+                    let reverse = ctx.reverse;
+                    parse_quote! {
+                        match if __lighter_internal_iter.eat_word(&[#(#lits),*], #reverse) {
+                            ::core::option::Option::None
+                        } else {
+                            #slow
+                        } {
+                            #[allow(unreachable_code)]
+                            ::core::option::Option::Some(__lighter_internal_result) => {
+                                __lighter_internal_result
                             }
+                            #[allow(unreachable_code)]
+                            ::core::option::Option::None => #fast,
                         }
                     }
+                } else {
+                    slow
+                }
+            } else {
+                make_loop(ctx, &looped, &tried)
+            };
+            (now, expr)
+        }
+    };
+
+    // the arms we could decide on before reading run first, from the last
+    // one backwards so that each one's else branch is the next
+    for &accept in now.iter().rev() {
+        let (guard, body) = arm_parts(ctx, accept, path.as_deref().map(|path| &path[..]), false);
+        let guard = guard.expect("only the last arm to run here can be unguarded");
+        expr = parse_quote! {
+            if #guard {
+                #body
+            } else {
+                #expr
+            }
+        };
+    }
+
+    expr
+}
+
+// the edges out of `state` that need an arm of their own, leaving out the
+// ones to a state where only the arms that already matched still match (the
+// arm for bytes no other arm wants handles those the same way)
+fn real_edges<'a>(
+    ctx: &'a Context,
+    state: usize,
+) -> impl Iterator<Item = &'a (ByteSet, Span, usize)> {
+    let node = &ctx.states[state];
+    let longest = ctx.opts.prefer == Prefer::Longest;
+    node.edges.iter().filter(move |&&(_, _, to)| {
+        let next = &ctx.states[to];
+        next.sticky != node.sticky || next.live.iter().any(|&arm| longest || !next.is_sticky(arm))
+    })
+}
+
+// the `match` on the byte `next` read in `state`. the edges to the states in
+// `looped` are handled by the loop that read the byte, so they can't happen
+fn read_match(
+    ctx: &Context,
+    state: usize,
+    next: Expr,
+    mut path: Option<&mut Vec<TokenStream2>>,
+    tried: &[usize],
+    looped: &HashMap<usize, usize>,
+) -> ExprMatch {
+    let node = &ctx.states[state];
+    let mut err: Expr = parse_quote!(::core::result::Result::Err(e));
+    if ctx.word_end.is_some() {
+        err = parse_quote!(::core::option::Option::Some(#err));
+    }
+    let mut m: ExprMatch = parse_quote! {
+        match #next {
+            ::core::option::Option::Some(::core::result::Result::Err(e)) => #err,
+        }
+    };
+
+    let mut edges: Vec<_> = real_edges(ctx, state).collect();
+    edges.sort_by_key(|(bytes, _, _)| bytes.iter().next());
+    let mut covered = ByteSet::default();
+    let mut in_loop = ByteSet::default();
+    for &(bytes, span, to) in edges {
+        covered = covered.union(&bytes);
+        if looped.contains_key(&to) {
+            in_loop = in_loop.union(&bytes);
+            continue;
+        }
+
+        let mut pat = byte_pat(&bytes, span);
+
+        // if more than one byte leads here, we don't know which one
+        // was read without binding it
+        let expr = match path.as_deref_mut() {
+            Some(path) => {
+                if bytes.len() == 1 {
+                    let b = byte_lit(bytes.iter().next().unwrap(), span);
+                    path.push(quote!(#b));
+                } else {
+                    let byte = format_ident!("__lighter_internal_byte_{}", path.len());
+                    pat = parse_quote!(#byte @ (#pat));
+                    path.push(quote!(#byte));
+                }
+
+                let expr = make_match(ctx, to, Some(&mut *path), tried);
+                path.pop();
+                expr
+            }
+            None => make_match(ctx, to, None, tried),
+        };
+
+        m.arms.push(parse_quote! {
+            ::core::option::Option::Some(::core::result::Result::Ok(#pat)) => #expr,
+        });
+    }
+
+    if !in_loop.is_empty() {
+        let pat = byte_pat(&in_loop, Span::call_site());
+        m.arms.push(parse_quote! {
+            ::core::option::Option::Some(::core::result::Result::Ok(#pat)) => ::core::unreachable!(),
+        });
+    }
+
+    let path = path.as_deref().map(|path| &path[..]);
+    let untried = |accepts: &[Accept]| -> Vec<Accept> {
+        first_cases(accepts)
+            .into_iter()
+            .filter(|accept| !tried.contains(&accept.arm))
+            .collect()
+    };
+
+    // the input ended here; the arms that match the whole string
+    // compete with the prefixes that matched along the way
+    insert_wild(
+        ctx,
+        &mut m,
+        &parse_quote!(::core::option::Option::None),
+        untried(&node.end),
+        path,
+        false,
+    );
+
+    // we read a byte that no arm wants
+    if covered.len() <= u8::MAX as usize {
+        insert_wild(
+            ctx,
+            &mut m,
+            &parse_quote! {
+                ::core::option::Option::Some(
+                    ::core::result::Result::Ok(__lighter_internal_last_byte)
+                )
+            },
+            untried(&node.sticky),
+            path,
+            true,
+        );
+    }
+
+    m
+}
+
+// the states (starting with `state` itself) we could read our way through
+// and end up back at `state` without any arm being able to run in between,
+// or nothing if there aren't any
+fn find_loop(ctx: &Context, state: usize, tried: &[usize]) -> Vec<usize> {
+    // the states we would just read another byte in
+    let reads = |state: usize| {
+        let node = &ctx.states[state];
+        let longest = ctx.opts.prefer == Prefer::Longest;
+        node.below.is_some_and(|below| {
+            node.sticky.iter().all(|accept| {
+                longest
+                    || tried.contains(&accept.arm)
+                    || below < accept.arm
+                    || accept.case.is_none()
+            })
+        })
+    };
+
+    // every state reachable from `state`, then the ones of those that can
+    // get back to it
+    let mut reachable = vec![state];
+    let mut seen = vec![false; ctx.states.len()];
+    seen[state] = true;
+    let mut i = 0;
+    while i < reachable.len() {
+        for &(_, _, to) in real_edges(ctx, reachable[i]) {
+            if !seen[to] && reads(to) {
+                seen[to] = true;
+                reachable.push(to);
+            }
+        }
+        i += 1;
+    }
+
+    let mut looped = vec![false; ctx.states.len()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &from in &reachable {
+            if !looped[from] && real_edges(ctx, from).any(|&(_, _, to)| to == state || looped[to]) {
+                looped[from] = true;
+                changed = true;
+            }
+        }
+    }
+
+    // keep `state` first, since the loop starts there
+    let mut looped: Vec<usize> = reachable.into_iter().filter(|&s| looped[s]).collect();
+    looped.sort_by_key(|&s| s != state);
+    looped
+}
+
+// read bytes for as long as we stay in the states in `looped` (which starts
+// with the state we are in), then match on the byte that took us out. no user
+// code can run inside the loop, where a `break` or `continue` in an arm would
+// end up applying to our loop instead of the user's
+fn make_loop(ctx: &Context, looped: &[usize], tried: &[usize]) -> Expr {
+    let next = next_byte(ctx.reverse, Span::call_site());
+    let index: HashMap<usize, usize> = looped.iter().enumerate().map(|(i, &s)| (s, i)).collect();
+
+    // for each state in the loop, the bytes that keep us in the loop
+    // and the state they lead to (if there's more than one)
+    let steps = looped.iter().map(|&state| {
+        let arms = real_edges(ctx, state)
+            .filter_map(|(bytes, span, to)| Some((bytes, span, index.get(to)?)))
+            .map(|(bytes, span, to)| {
+                let pat = byte_pat(bytes, *span);
+                let to = match looped.len() {
+                    1 => quote!({}),
+                    _ => quote!(#to),
+                };
+                quote!(::core::option::Option::Some(::core::result::Result::Ok(#pat)) => #to,)
+            });
+
+        quote! {
+            match __lighter_internal_next {
+                #(#arms)*
+                _ => break __lighter_internal_next,
+            }
+        }
+    });
+
+    let exits = looped.iter().map(|&state| {
+        read_match(
+            ctx,
+            state,
+            parse_quote!(__lighter_internal_next),
+            None,
+            tried,
+            &index,
+        )
+    });
+
+    if looped.len() == 1 {
+        let step = steps.into_iter().next().unwrap();
+        let exit = exits.into_iter().next().unwrap();
+        return parse_quote! {
+            {
+                let __lighter_internal_next = loop {
+                    let __lighter_internal_next = __lighter_internal_iter.#next();
+                    #step;
+                };
+                #exit
+            }
+        };
+    }
+
+    let indices = 0..looped.len();
+    let indices2 = indices.clone();
+    parse_quote! {
+        {
+            let mut __lighter_internal_state: usize = 0;
+            let __lighter_internal_next = loop {
+                let __lighter_internal_next = __lighter_internal_iter.#next();
+                __lighter_internal_state = match __lighter_internal_state {
+                    #(#indices => #steps,)*
+                    _ => ::core::unreachable!(),
+                };
+            };
+            match __lighter_internal_state {
+                #(#indices2 => #exits,)*
+                _ => ::core::unreachable!(),
+            }
+        }
+    }
+}
+
+// generate the code for the DFA as a static transition table (indexed by
+// byte class) and a loop that runs through it, then a match on the arm it
+// picked. this is a lot less code than a match for every state, but the
+// guards have to run inside the loop, since reading on after one fails
+// means going back into it
+fn make_table(ctx: &Context) -> ExprMatch {
+    let next = next_byte(ctx.reverse, Span::call_site());
+    let longest = ctx.opts.prefer == Prefer::Longest;
+    let lit = |n: usize| proc_macro2::Literal::usize_unsuffixed(n);
+    let dead = lit(u16::MAX as usize);
+
+    // the arms each state can run, as indices into `accepts`
+    let mut accepts: Vec<Accept> = Vec::new();
+    let mut index = HashMap::new();
+    let mut list = |list: &[Accept]| {
+        let list = first_cases(list).into_iter().map(|accept| {
+            let i = *index.entry(accept).or_insert_with(|| {
+                accepts.push(accept);
+                accepts.len() - 1
+            });
+            lit(i)
+        });
+        quote!(&[#(#list),*])
+    };
+    let sticky: Vec<_> = ctx.states.iter().map(|node| list(&node.sticky)).collect();
+    let end: Vec<_> = ctx.states.iter().map(|node| list(&node.end)).collect();
+    let below = ctx.states.iter().map(|node| match node.below {
+        Some(below) => lit(below),
+        None => dead.clone(),
+    });
+
+    let mut classes = [0; 256];
+    for (i, class) in ctx.classes.iter().enumerate() {
+        for byte in class.iter() {
+            classes[byte as usize] = i;
+        }
+    }
+    let classes = classes.map(lit);
+    let table = ctx.states.iter().map(|node| {
+        let row = ctx.classes.iter().map(|class| {
+            let byte = class.iter().next().expect("classes aren't empty");
+            match node.edges.iter().find(|(bytes, _, _)| bytes.contains(byte)) {
+                Some(&(_, _, to)) => lit(to),
+                None => dead.clone(),
+            }
+        });
+        quote!([#(#row),*])
+    });
+
+    // an arm waits in a state if another arm could still match there and
+    // should run first, as in make_match
+    let arms = accepts.iter().map(|accept| lit(accept.arm));
+    let waits = accepts
+        .iter()
+        .map(|accept| longest || accept.case.is_none());
+    let guards = accepts.iter().enumerate().filter_map(|(i, &accept)| {
+        let (guard, _) = arm_parts(ctx, accept, None, false);
+        let i = lit(i);
+        guard.map(|guard| quote!(#i => #guard,))
+    });
+    let bodies = accepts.iter().enumerate().map(|(i, &accept)| {
+        let (_, body) = arm_parts(ctx, accept, None, false);
+        let attrs = &ctx.arms[accept.arm].attrs;
+        let i = lit(i);
+        quote! {
+            #(#attrs)*
+            #[allow(unreachable_code)]
+            ::core::result::Result::Ok(#i) => #body,
+        }
+    });
+
+    let (n_states, n_classes) = (ctx.states.len(), ctx.classes.len());
+    let (n_accepts, n_arms) = (accepts.len(), ctx.arms.len());
+    parse_quote! {
+        match {
+            static __LIGHTER_INTERNAL_CLASSES: [u8; 256] = [#(#classes),*];
+            static __LIGHTER_INTERNAL_TABLE: [[u16; #n_classes]; #n_states] = [#(#table),*];
+            static __LIGHTER_INTERNAL_STICKY: [&[u16]; #n_states] = [#(#sticky),*];
+            static __LIGHTER_INTERNAL_END: [&[u16]; #n_states] = [#(#end),*];
+            static __LIGHTER_INTERNAL_BELOW: [u16; #n_states] = [#(#below),*];
+            static __LIGHTER_INTERNAL_ARMS: [u16; #n_accepts] = [#(#arms),*];
+            static __LIGHTER_INTERNAL_WAITS: [bool; #n_accepts] = [#(#waits),*];
+
+            let mut __lighter_internal_state = 0;
+            let mut __lighter_internal_tried = [false; #n_arms];
+            // the arms to try next, and whether we could still read on
+            // instead of trying the ones that have to wait
+            let mut __lighter_internal_list = __LIGHTER_INTERNAL_STICKY[0];
+            let mut __lighter_internal_reading = true;
+            loop {
+                let __lighter_internal_below = __LIGHTER_INTERNAL_BELOW[__lighter_internal_state];
+                let mut __lighter_internal_found = ::core::option::Option::None;
+                for &__lighter_internal_accept in __lighter_internal_list {
+                    let __lighter_internal_arm = __LIGHTER_INTERNAL_ARMS[__lighter_internal_accept as usize];
+                    if __lighter_internal_tried[__lighter_internal_arm as usize] {
+                        continue;
+                    }
+                    if __lighter_internal_reading
+                        && __lighter_internal_below != #dead
+                        && (__lighter_internal_below < __lighter_internal_arm
+                            || __LIGHTER_INTERNAL_WAITS[__lighter_internal_accept as usize])
+                    {
+                        break;
+                    }
+
+                    let __lighter_internal_guard = match __lighter_internal_accept {
+                        #(#guards)*
+                        _ => true,
+                    };
+                    if __lighter_internal_guard {
+                        __lighter_internal_found = ::core::option::Option::Some(__lighter_internal_accept);
+                        break;
+                    }
+                    __lighter_internal_tried[__lighter_internal_arm as usize] = true;
+                }
+
+                if let ::core::option::Option::Some(__lighter_internal_accept) = __lighter_internal_found {
+                    break ::core::result::Result::Ok(__lighter_internal_accept);
+                }
+                if !__lighter_internal_reading {
+                    ::core::unreachable!();
+                }
+
+                match __lighter_internal_iter.#next() {
+                    ::core::option::Option::Some(::core::result::Result::Err(e)) => {
+                        break ::core::result::Result::Err(e);
+                    }
+                    ::core::option::Option::None => {
+                        __lighter_internal_list = __LIGHTER_INTERNAL_END[__lighter_internal_state];
+                        __lighter_internal_reading = false;
+                    }
+                    ::core::option::Option::Some(::core::result::Result::Ok(__lighter_internal_byte)) => {
+                        let __lighter_internal_class = __LIGHTER_INTERNAL_CLASSES[__lighter_internal_byte as usize];
+                        match __LIGHTER_INTERNAL_TABLE[__lighter_internal_state][__lighter_internal_class as usize] {
+                            #dead => __lighter_internal_reading = false,
+                            __lighter_internal_to => {
+                                __lighter_internal_state = __lighter_internal_to as usize;
+                                __lighter_internal_list = __LIGHTER_INTERNAL_STICKY[__lighter_internal_state];
+                            }
+                        }
+                    }
+                }
+            }
+        } {
+            #(#bodies)*
+            ::core::result::Result::Err(e) => ::core::result::Result::Err(e),
+            _ => ::core::unreachable!(),
+        }
+    }
+}
+
+// how much of the input a literal pattern has to match
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Anchor {
+    // the whole input, like a literal in a regular match statement
+    Whole,
+    // the start of the input, no matter what comes after (Prefix)
+    Start,
+    // the end of the input, no matter what comes before (Suffix)
+    End,
+}
+
+// the text a literal pattern was written as, which `@` patterns bind
+#[derive(Clone)]
+enum Text {
+    Str(String),
+    Bytes(Vec<u8>),
+}
+
+impl Text {
+    fn as_bytes(&self) -> &[u8] {
+        match self {
+            Text::Str(s) => s.as_bytes(),
+            Text::Bytes(b) => b,
+        }
+    }
+
+    fn concat(&self, other: &Text) -> Text {
+        match (self, other) {
+            (Text::Str(a), Text::Str(b)) => Text::Str(format!("{}{}", a, b)),
+            (a, b) => Text::Bytes([a.as_bytes(), b.as_bytes()].concat()),
+        }
+    }
+}
+
+// one way for a pattern to match: a set of bytes for each byte of the input,
+// and the text of the pattern, if it stands for a single string (byte ranges
+// don't, but IgnoreAsciiCase(...) patterns have the text they were written as)
+struct Literal {
+    bytes: Vec<ByteSet>,
+    text: Option<Text>,
+}
+
+impl Literal {
+    // single bytes become literals, which Hir::concat joins up, so that the
+    // Hir knows whether the text is valid UTF-8
+    fn hir(&self) -> Hir {
+        Hir::concat(
+            self.bytes
+                .iter()
+                .map(|set| match set.len() {
+                    1 => Hir::literal([set.iter().next().unwrap()]),
+                    _ => {
+                        let ranges = set.ranges().into_iter();
+                        let ranges = ranges.map(|(lo, hi)| ClassBytesRange::new(lo, hi));
+                        Hir::class(Class::Bytes(ClassBytes::new(ranges)))
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+// one way for an arm to match, waiting to be compiled into the DFA
+struct Case {
+    hir: Hir,
+    // how many bytes the case matches, if that's always the same
+    len: Option<usize>,
+    text: Option<Text>,
+    span: Span,
+    arm: usize,
+    anchor: Anchor,
+    rest: Option<PatIdent>,
+    // whether the rest of the input is sure to start on a char boundary
+    // if the input is a str
+    aligned: bool,
+    // how to find the parts of the input a Seq(...) binds names to, and
+    // whether they go all the way to its end, so that we can find how many
+    // bytes it matched even if that isn't always the same
+    steps: Vec<Step>,
+    measured: bool,
+}
+
+impl Case {
+    fn new(hir: Hir, text: Option<Text>, span: Span, arm: usize, anchor: Anchor) -> Self {
+        let props = hir.properties();
+        let len = props
+            .minimum_len()
+            .filter(|&min| props.maximum_len() == Some(min));
+        Case {
+            hir,
+            len,
+            text,
+            span,
+            arm,
+            anchor,
+            rest: None,
+            aligned: false,
+            steps: Vec::new(),
+            measured: false,
+        }
+    }
+
+    // whether a Seq(...) in the case binds part of the input to a name
+    fn binds(&self) -> bool {
+        self.steps
+            .iter()
+            .any(|step| matches!(step, Step::Segment(_, Some(_), _)))
+    }
+
+    // whether the iterator has to keep the bytes it reads for the arm to find
+    // the parts of the input it binds, or to give back the ones it read past
+    // a prefix
+    fn walks(&self) -> bool {
+        self.binds() || self.rest.is_some()
+    }
+}
+
+// a glob like "*.rs", where `*` matches any run of bytes and `?` matches any
+// one char (or byte, for a byte string glob). a `\` matches the next byte
+// as-is, so that "\\*" only matches "*"
+fn glob_hir(glob: &[u8], any: Dot, ignore_case: bool) -> Option<Hir> {
+    let mut hirs = Vec::new();
+    let mut bytes = glob.iter();
+    while let Some(&b) = bytes.next() {
+        let b = match b {
+            b'*' => {
+                hirs.push(Hir::repetition(Repetition {
+                    min: 0,
+                    max: None,
+                    greedy: true,
+                    sub: Box::new(Hir::dot(Dot::AnyByte)),
+                }));
+                continue;
+            }
+            b'?' => {
+                hirs.push(Hir::dot(any));
+                continue;
+            }
+            b'\\' => *bytes.next()?,
+            b => b,
+        };
+
+        let set = ByteSet::from(b);
+        let set = if ignore_case {
+            set.ignore_ascii_case()
+        } else {
+            set
+        };
+        hirs.push(
+            Literal {
+                bytes: vec![set],
+                text: None,
+            }
+            .hir(),
+        );
+    }
+    Some(Hir::concat(hirs))
+}
+
+// a part of a Seq(...) whose length depends on the input, which can be bound
+// to a name like `Seq("key=", value @ Until(b';'), ";")`
+#[derive(Clone, Copy)]
+enum Segment {
+    // any bytes up to (but not including) this one
+    Until(u8),
+    // one or more ASCII digits
+    Digits,
+    // exactly this many bytes
+    Take(u32),
+}
+
+impl Segment {
+    fn hir(&self) -> Hir {
+        let class = |set: ByteSet| {
+            let ranges = set.ranges().into_iter();
+            let ranges = ranges.map(|(lo, hi)| ClassBytesRange::new(lo, hi));
+            Box::new(Hir::class(Class::Bytes(ClassBytes::new(ranges))))
+        };
+        let (min, max, sub) = match *self {
+            Segment::Until(b) => (
+                0,
+                None,
+                class(ByteSet::range(0, u8::MAX).difference(&b.into())),
+            ),
+            Segment::Digits => (1, None, class(ByteSet::range(b'0', b'9'))),
+            Segment::Take(n) => (n, Some(n), class(ByteSet::range(0, u8::MAX))),
+        };
+        Hir::repetition(Repetition {
+            min,
+            max,
+            greedy: true,
+            sub,
+        })
+    }
+
+    // the code to move __lighter_internal_pos past this segment
+    fn skip(&self) -> TokenStream2 {
+        let rest = quote!(__lighter_internal_input[__lighter_internal_pos..]);
+        let len = match *self {
+            Segment::Until(b) => {
+                let b = byte_lit(b, Span::call_site());
+                quote!(#rest.iter().position(|&b| b == #b).unwrap_or(#rest.len()))
+            }
+            Segment::Digits => quote!(#rest.iter().take_while(|b| b.is_ascii_digit()).count()),
+            Segment::Take(n) => {
+                let n = n as usize;
+                quote!(#n)
+            }
+        };
+        quote!(__lighter_internal_pos += #len;)
+    }
+}
+
+// the code to move __lighter_internal_pos past `steps`, starting from the
+// start of the input in __lighter_internal_input
+fn walk(steps: &[Step]) -> TokenStream2 {
+    let skips = steps.iter().map(|step| match step {
+        Step::Skip(len) => quote!(__lighter_internal_pos += #len;),
+        Step::Segment(segment, ..) => segment.skip(),
+    });
+    quote! {
+        let __lighter_internal_input = Input::input(&__lighter_internal_iter);
+        let mut __lighter_internal_pos: usize = 0;
+        #(#skips)*
+    }
+}
+
+// one part of a Seq(...) that binds names, in order
+enum Step {
+    // a part that always matches this many bytes
+    Skip(usize),
+    // a segment, the name it's bound to, and whether the part of the input
+    // it matches is sure to start and end on char boundaries if the input is
+    // a str
+    Segment(Segment, Option<PatIdent>, bool),
+}
+
+// the segment `pat` stands for (Digits, Until(...) or Take(...)), if any
+fn segment(pat: &Pat) -> syn::Result<Option<Segment>> {
+    let expr = match pat {
+        Pat::Ident(PatIdent {
+            by_ref: None,
+            mutability: None,
+            ident,
+            subpat: None,
+            ..
+        }) if ident == "Digits" => return Ok(Some(Segment::Digits)),
+        Pat::TupleStruct(expr) if expr.path.is_ident("Until") || expr.path.is_ident("Take") => expr,
+        _ => return Ok(None),
+    };
+
+    let lit = match expr.elems.iter().collect::<Vec<_>>()[..] {
+        [Pat::Lit(PatLit { lit, .. })] => Some(lit),
+        _ => None,
+    };
+    if expr.path.is_ident("Until") {
+        match lit {
+            Some(Lit::Byte(b)) => Ok(Some(Segment::Until(b.value()))),
+            Some(Lit::Char(c)) if c.value().is_ascii() => Ok(Some(Segment::Until(c.value() as u8))),
+            _ => Err(syn::Error::new(
+                expr.paren_token.span.join(),
+                "Until(...) takes one byte or ASCII char literal",
+            )),
+        }
+    } else {
+        match lit {
+            Some(Lit::Int(n)) => match n.base10_parse() {
+                Ok(n) => Ok(Some(Segment::Take(n))),
+                Err(_) => Err(syn::Error::new_spanned(
+                    n,
+                    format!("Take(...) can take at most {} bytes", u32::MAX),
+                )),
+            },
+            _ => Err(syn::Error::new(
+                expr.paren_token.span.join(),
+                "Take(...) takes one integer literal",
+            )),
+        }
+    }
+}
+
+// the bytes a match for `hir` could start with, e.g. to tell whether a
+// segment like Until(b';') is sure to stop where the next part starts
+fn first_bytes(hir: &Hir) -> ByteSet {
+    let mut nfa = Nfa {
+        states: Vec::new(),
+        reverse: false,
+        longest: false,
+    };
+    let start = nfa.add();
+    nfa.compile(hir, start, Span::call_site());
+    nfa.closure(vec![start])
+        .into_iter()
+        .flat_map(|state| nfa.states[state].edges.iter().map(|&(bytes, _, _)| bytes))
+        .fold(ByteSet::default(), |set, bytes| set.union(&bytes))
+}
+
+// the bytes a match for `hir` could end with
+fn last_bytes(hir: &Hir) -> ByteSet {
+    let mut nfa = Nfa {
+        states: Vec::new(),
+        reverse: true,
+        longest: false,
+    };
+    let start = nfa.add();
+    nfa.compile(hir, start, Span::call_site());
+    nfa.closure(vec![start])
+        .into_iter()
+        .flat_map(|state| nfa.states[state].edges.iter().map(|&(bytes, _, _)| bytes))
+        .fold(ByteSet::default(), |set, bytes| set.union(&bytes))
+}
+
+// the bytes that can only come in the middle of a UTF-8 character
+fn continuation() -> ByteSet {
+    ByteSet::range(0x80, 0xbf)
+}
+
+// whether a match for `hir` from the start of a str is sure to end on a char
+// boundary: it does if what it matched is valid UTF-8 on its own, or if it
+// always ends in an ASCII byte
+fn ends_on_char(hir: &Hir) -> bool {
+    hir.properties().is_utf8()
+        || last_bytes(hir)
+            .difference(&ByteSet::range(0, 0x7f))
+            .is_empty()
+}
+
+// whether a match for `hir` that ends at the end of a str is sure to start on
+// a char boundary, which it does unless it can start with a continuation byte
+fn starts_on_char(hir: &Hir) -> bool {
+    first_bytes(hir).intersection(&continuation()).is_empty()
+}
+
+// the shortest input (in the order the DFA reads it) that no unguarded arm
+// matches, if there is any
+fn unmatched(states: &[State], arms: &[Arm]) -> Option<Vec<u8>> {
+    let unguarded = |accepts: &[Accept]| {
+        accepts
+            .iter()
+            .any(|accept| arms[accept.arm].guard.is_none())
+    };
+    // a byte from `bytes` that looks good in an error message
+    let pick = |bytes: &ByteSet| {
+        let mut printable = bytes.iter().filter(|b| b.is_ascii_alphanumeric());
+        printable.next().or_else(|| bytes.iter().next())
+    };
+
+    let mut paths: Vec<Option<Vec<u8>>> = vec![None; states.len()];
+    paths[0] = Some(Vec::new());
+    let mut queue = std::collections::VecDeque::from([0]);
+    while let Some(state) = queue.pop_front() {
+        let node = &states[state];
+        let path = paths[state].clone().unwrap();
+        if !unguarded(&node.end) {
+            return Some(path);
+        }
+
+        let mut covered = ByteSet::default();
+        for (bytes, _, to) in &node.edges {
+            covered = covered.union(bytes);
+            if paths[*to].is_none() {
+                let mut next = path.clone();
+                next.push(pick(bytes).unwrap());
+                paths[*to] = Some(next);
+                queue.push_back(*to);
+            }
+        }
+
+        let uncovered = ByteSet::range(0, u8::MAX).difference(&covered);
+        if let (Some(b), false) = (pick(&uncovered), unguarded(&node.sticky)) {
+            return Some([&path[..], &[b]].concat());
+        }
+    }
+
+    None
+}
+
+// a statement that makes rustc warn with `msg` at `span`, since there is no
+// stable way for a proc macro to emit a warning itself
+fn warning(span: Span, msg: &str) -> TokenStream2 {
+    quote_spanned! {span=>
+        {
+            #[deprecated(note = #msg)]
+            fn __lighter_internal_warning() {}
+            __lighter_internal_warning();
+        }
+    }
+}
+
+// the patterns we know how to turn into a trie, for error messages
+const EXPECTED_PAT: &str = "expected a string, byte string, C string, byte or char literal, \
+                            a byte range, `[...]`, `Any(...)`, `Regex(...)`, `Glob(...)`, \
+                            `Many(...)`, `Many1(...)`, `Opt(...)`, `Seq(...)`, `Until(...)`, \
+                            `Take(...)`, `Digits`, `Prefix(...)`, `Suffix(...)`, \
+                            `IgnoreAsciiCase(...)`, `_` or an identifier";
+const EXPECTED_LIT: &str =
+    "lighter! can only match string, byte string, C string, byte and char literals";
+
+// the byte value of one end of a range pattern
+fn range_end(expr: &Expr) -> syn::Result<u8> {
+    if let Expr::Lit(expr) = expr {
+        match &expr.lit {
+            Lit::Byte(b) => return Ok(b.value()),
+            Lit::Char(c) if c.value().is_ascii() => return Ok(c.value() as u8),
+            Lit::Char(c) => {
+                return Err(syn::Error::new_spanned(
+                    c,
+                    "lighter! matches one byte at a time, so char ranges have to be ASCII",
+                ))
+            }
+            _ => {}
+        }
+    }
+
+    Err(syn::Error::new_spanned(
+        expr,
+        "the ends of a range pattern have to be byte or ASCII char literals",
+    ))
+}
+
+// the literals a pattern can match, one for each alternative
+// (e.g. for each case of an or-pattern). this handles the patterns that can
+// be used both as a whole arm and as part of a sequence (`[...]`) pattern
+fn pat_bytes(pat: &Pat, ignore_case: bool) -> syn::Result<Vec<Literal>> {
+    // with IgnoreAsciiCase(...), each letter can be either case
+    let fold = |set: ByteSet| {
+        if ignore_case {
+            set.ignore_ascii_case()
+        } else {
+            set
+        }
+    };
+    let lit = |text: Text| {
+        Ok(vec![Literal {
+            bytes: text.as_bytes().iter().map(|&b| fold(b.into())).collect(),
+            text: Some(text),
+        }])
+    };
+    // a single byte from a set, which doesn't have text of its own
+    let class = |set: ByteSet| {
+        Ok(vec![Literal {
+            bytes: vec![fold(set)],
+            text: None,
+        }])
+    };
+
+    match pat {
+        Pat::Lit(expr) => match &expr.lit {
+            Lit::Str(s) => lit(Text::Str(s.value())),
+            Lit::ByteStr(s) => lit(Text::Bytes(s.value())),
+            Lit::CStr(s) => lit(Text::Bytes(s.value().into_bytes_with_nul())),
+            Lit::Byte(b) => lit(Text::Bytes(vec![b.value()])),
+            Lit::Char(c) => lit(Text::Str(c.value().to_string())),
+            lit => Err(syn::Error::new_spanned(lit, EXPECTED_LIT)),
+        },
+        Pat::Range(range) => {
+            let lo = range.start.as_deref().map(range_end).transpose()?;
+            let hi = range.end.as_deref().map(range_end).transpose()?;
+            let hi = match (hi, range.limits) {
+                (Some(0), RangeLimits::HalfOpen(_)) => None,
+                (Some(hi), RangeLimits::HalfOpen(_)) => Some(hi - 1),
+                (hi, _) => Some(hi.unwrap_or(u8::MAX)),
+            };
+
+            match (lo.unwrap_or(0), hi) {
+                (lo, Some(hi)) if lo <= hi => class(ByteSet::range(lo, hi)),
+                _ => Err(syn::Error::new_spanned(
+                    range,
+                    "this range doesn't match any bytes",
+                )),
+            }
+        }
+        Pat::TupleStruct(expr) if expr.path.is_ident("Any") => {
+            let bytes = match expr.elems.iter().collect::<Vec<_>>()[..] {
+                [Pat::Lit(PatLit {
+                    lit: Lit::Str(s), ..
+                })] if s.value().is_ascii() => s.value().into_bytes(),
+                [Pat::Lit(PatLit {
+                    lit: Lit::ByteStr(s),
+                    ..
+                })] => s.value(),
+                _ => {
+                    return Err(syn::Error::new(
+                        expr.paren_token.span.join(),
+                        "Any(...) takes one ASCII string or byte string literal, \
+                         and matches any one of its bytes",
+                    ))
+                }
+            };
+
+            let mut set = ByteSet::default();
+            for b in bytes {
+                set.insert(b);
+            }
+            class(set)
+        }
+        Pat::TupleStruct(expr)
+            if expr.path.is_ident("IgnoreAsciiCase") && expr.elems.len() == 1 =>
+        {
+            pat_bytes(expr.elems.first().unwrap(), true)
+        }
+        Pat::Slice(slice) => {
+            // every combination of the alternatives for each element in turn
+            let mut seqs = vec![Literal {
+                bytes: Vec::new(),
+                text: Some(Text::Str(String::new())),
+            }];
+            for elem in &slice.elems {
+                let alts = pat_bytes(elem, ignore_case)?;
+                seqs = seqs
+                    .iter()
+                    .flat_map(|seq| {
+                        alts.iter().map(move |alt| Literal {
+                            bytes: seq.bytes.iter().chain(&alt.bytes).copied().collect(),
+                            text: seq
+                                .text
+                                .as_ref()
+                                .zip(alt.text.as_ref())
+                                .map(|(a, b)| a.concat(b)),
+                        })
+                    })
+                    .collect();
+            }
+            Ok(seqs)
+        }
+        Pat::Or(expr) => {
+            let mut alts = Vec::new();
+            for pat in &expr.cases {
+                alts.extend(pat_bytes(pat, ignore_case)?);
+            }
+            Ok(alts)
+        }
+        Pat::Paren(expr) => pat_bytes(&expr.pat, ignore_case),
+        pat => Err(syn::Error::new_spanned(pat, EXPECTED_PAT)),
+    }
+}
+
+fn parse_arm(
+    opts: &Options,
+    cases: &mut Vec<Case>,
+    wild: &mut Vec<usize>,
+    pat: Pat,
+    arm: usize,
+    anchor: Anchor,
+    ignore_case: bool,
+) -> syn::Result<()> {
+    let attrs = match &pat {
+        Pat::Ident(pat) => &pat.attrs,
+        Pat::Lit(pat) => &pat.attrs,
+        Pat::Or(pat) => &pat.attrs,
+        Pat::Paren(pat) => &pat.attrs,
+        Pat::Range(pat) => &pat.attrs,
+        Pat::Slice(pat) => &pat.attrs,
+        Pat::TupleStruct(pat) => &pat.attrs,
+        Pat::Wild(pat) => &pat.attrs,
+        _ => &[][..],
+    };
+    if let Some(attr) = attrs.first() {
+        return Err(syn::Error::new_spanned(
+            attr,
+            "lighter! doesn't support attributes on patterns",
+        ));
+    }
+
+    if let Some(segment) = segment(&pat)? {
+        cases.push(Case::new(segment.hir(), None, pat.span(), arm, anchor));
+        return Ok(());
+    }
+
+    match pat {
+        Pat::TupleStruct(expr)
+            if ["Prefix", "Suffix", "IgnoreAsciiCase"]
+                .iter()
+                .any(|name| expr.path.is_ident(name)) =>
+        {
+            let name = expr.path.get_ident().unwrap().to_string();
+
+            // Prefix and Suffix can also bind the rest of the input
+            let takes_rest = name != "IgnoreAsciiCase";
+            if expr.elems.len() != 1 && !(takes_rest && expr.elems.len() == 2) {
+                let msg = if takes_rest {
+                    format!("{}(...) takes a pattern and optionally a binding for the rest of the input", name)
+                } else {
+                    format!("{}(...) takes exactly one pattern", name)
+                };
+                return Err(syn::Error::new(expr.paren_token.span.join(), msg));
+            }
+
+            let rest = match expr.elems.iter().nth(1) {
+                Some(Pat::Ident(
+                    rest @ PatIdent {
+                        by_ref: None,
+                        subpat: None,
+                        ..
+                    },
+                )) => Some(rest.clone()),
+                Some(pat) => {
+                    return Err(syn::Error::new_spanned(
+                        pat,
+                        format!(
+                            "{}(..., rest) can only bind the rest of the input to a name",
+                            name
+                        ),
+                    ))
+                }
+                None => None,
+            };
+
+            if let Some(pat @ (Pat::Ident(_) | Pat::Wild(_))) = expr.elems.first() {
+                return Err(syn::Error::new_spanned(
+                    pat,
+                    format!(
+                        "{}(...) can only hold literals; \
+                         a `_` or identifier pattern already matches any input on its own",
+                        name
+                    ),
+                ));
+            }
+
+            let pat = expr.elems.into_iter().next().unwrap();
+            match name.as_str() {
+                "IgnoreAsciiCase" => parse_arm(opts, cases, wild, pat, arm, anchor, true)?,
+                _ => {
+                    let inner = if name == "Prefix" {
+                        Anchor::Start
+                    } else {
+                        Anchor::End
+                    };
+
+                    if anchor != Anchor::Whole && anchor != inner {
+                        return Err(syn::Error::new_spanned(
+                            expr.path,
+                            "Prefix(...) and Suffix(...) can't be nested in each other",
+                        ));
+                    }
+
+                    let first = cases.len();
+                    parse_arm(opts, cases, wild, pat, arm, inner, ignore_case)?;
+                    if let Some(rest) = rest {
+                        if cases[first..].iter().any(|case| case.rest.is_some()) {
+                            return Err(syn::Error::new_spanned(
+                                rest,
+                                "the rest of the input is already bound by an inner pattern",
+                            ));
+                        }
+
+                        if let Some(case) = cases[first..]
+                            .iter()
+                            .find(|case| case.len.is_none() && !case.measured)
+                        {
+                            return Err(syn::Error::new(
+                                case.span,
+                                "the rest of the input can only be bound after a pattern \
+                                 that always matches the same number of bytes",
+                            ));
+                        }
+
+                        for case in &mut cases[first..] {
+                            case.rest = Some(rest.clone());
+                            case.aligned = match inner {
+                                Anchor::End => starts_on_char(&case.hir),
+                                _ => ends_on_char(&case.hir),
+                            };
+                        }
+                    }
+                }
+            }
+        }
+        Pat::TupleStruct(expr) if expr.path.is_ident("Regex") => {
+            let regex = match expr.elems.iter().collect::<Vec<_>>()[..] {
+                [Pat::Lit(PatLit {
+                    lit: Lit::Str(s), ..
+                })] => s.clone(),
+                _ => {
+                    return Err(syn::Error::new(
+                        expr.paren_token.span.join(),
+                        "Regex(...) takes one string literal",
+                    ))
+                }
+            };
+
+            let hir = ParserBuilder::new()
+                .utf8(false)
+                .case_insensitive(ignore_case || opts.case_insensitive)
+                .build()
+                .parse(&regex.value())
+                .map_err(|e| syn::Error::new_spanned(&regex, e))?;
+            if !hir.properties().look_set().is_empty() {
+                return Err(syn::Error::new_spanned(
+                    regex,
+                    "Regex(...) always matches the whole input (or the start or end of it, \
+                     inside Prefix(...) or Suffix(...)), so it can't use anchors or word boundaries",
+                ));
+            }
+
+            cases.push(Case::new(hir, None, regex.span(), arm, anchor));
+        }
+        Pat::TupleStruct(expr)
+            if ["Many", "Many1", "Opt", "Seq"]
+                .iter()
+                .any(|name| expr.path.is_ident(name)) =>
+        {
+            let name = expr.path.get_ident().unwrap().to_string();
+            let span = expr.span();
+            if name == "Seq" && expr.elems.is_empty() {
+                return Err(syn::Error::new(
+                    expr.paren_token.span.join(),
+                    "Seq(...) takes at least one pattern",
+                ));
+            } else if name != "Seq" && expr.elems.len() != 1 {
+                return Err(syn::Error::new(
+                    expr.paren_token.span.join(),
+                    format!("{}(...) takes exactly one pattern", name),
+                ));
+            }
+
+            // each pattern inside is parsed on its own, and the
+            // alternatives in it become one alternation
+            let mut hirs = Vec::new();
+            let mut steps = Vec::new();
+            let mut spans = Vec::new();
+            for pat in expr.elems {
+                let pat_span = pat.span();
+
+                // Seq(...) can bind the segments in it to names
+                let (binding, pat) = match pat {
+                    Pat::Ident(
+                        mut binding @ PatIdent {
+                            subpat: Some(_), ..
+                        },
+                    ) if segment(&binding.subpat.as_ref().unwrap().1)?.is_some() => {
+                        let (_, subpat) = binding.subpat.take().unwrap();
+                        if let Some(by_ref) = binding.by_ref {
+                            return Err(syn::Error::new_spanned(
+                                by_ref,
+                                "lighter! can't bind by reference",
+                            ));
+                        }
+                        if name != "Seq" {
+                            return Err(syn::Error::new(
+                                pat_span,
+                                format!(
+                                    "only Seq(...) can bind parts of the input, not {}(...)",
+                                    name
+                                ),
+                            ));
+                        }
+                        (Some(binding), *subpat)
+                    }
+                    pat => (None, pat),
+                };
+                spans.push(pat.span());
+                if let Some(segment) = segment(&pat)? {
+                    hirs.push(segment.hir());
+                    steps.push(Some(Step::Segment(segment, binding, false)));
+                    continue;
+                }
+
+                let (mut inner, mut inner_wild) = (Vec::new(), Vec::new());
+                parse_arm(
+                    opts,
+                    &mut inner,
+                    &mut inner_wild,
+                    pat,
+                    arm,
+                    Anchor::Whole,
+                    ignore_case,
+                )?;
+                if !inner_wild.is_empty() {
+                    return Err(syn::Error::new(
+                        pat_span,
+                        format!(
+                            "{}(...) can only hold literals; \
+                             a `_` or identifier pattern would match the rest of the input",
+                            name
+                        ),
+                    ));
+                }
+                if let Some(case) = inner.iter().find(|case| case.anchor != Anchor::Whole) {
+                    return Err(syn::Error::new(
+                        case.span,
+                        format!(
+                            "Prefix(...) and Suffix(...) have to go around {}(...), not inside it",
+                            name
+                        ),
+                    ));
+                }
+                if let Some(case) = inner.iter().find(|case| case.binds()) {
+                    return Err(syn::Error::new(
+                        case.span,
+                        format!("{}(...) can't hold a Seq(...) that binds names", name),
+                    ));
+                }
+                let hir = Hir::alternation(inner.into_iter().map(|case| case.hir).collect());
+                let props = hir.properties();
+                let len = props
+                    .minimum_len()
+                    .filter(|&min| props.maximum_len() == Some(min));
+                steps.push(len.map(Step::Skip));
+                hirs.push(hir);
+            }
+
+            // a part of a str is only a str if it starts and ends on char
+            // boundaries, which a position in it is sure to be on if what
+            // comes before is always valid UTF-8, or if what comes after can't
+            // start with a continuation byte (or run into the rest of the
+            // input past a Prefix(...))
+            let on_char = |at: usize| {
+                let after = Hir::concat(hirs[at..].to_vec());
+                ends_on_char(&Hir::concat(hirs[..at].to_vec()))
+                    || starts_on_char(&after)
+                        && (after.properties().minimum_len() != Some(0) || anchor == Anchor::Whole)
+            };
+            for (i, step) in steps.iter_mut().enumerate() {
+                if let Some(Step::Segment(_, Some(_), aligned)) = step {
+                    *aligned = on_char(i) && on_char(i + 1);
+                }
+            }
+
+            // we find the bound parts (and the end of the Seq(...), for a
+            // rest binding) after the match by skipping over the parts before
+            // them, which only works as far as it's clear where each one ends
+            let parts = steps.len();
+            let bound = steps
+                .iter()
+                .rposition(|step| matches!(step, Some(Step::Segment(_, Some(_), _))));
+            let mut known = Vec::new();
+            let mut unclear = None;
+            for (i, step) in steps.into_iter().enumerate() {
+                let step = match step {
+                    Some(step) => step,
+                    None => {
+                        unclear = Some(syn::Error::new(
+                            spans[i],
+                            "the parts of a Seq(...) before a bound part have to always \
+                             match the same number of bytes, or be Until(...), Digits or Take(...)",
+                        ));
+                        break;
+                    }
+                };
+
+                let stops = match step {
+                    Step::Segment(Segment::Until(b), ..) => Some(ByteSet::from(b)),
+                    Step::Segment(Segment::Digits, ..) => {
+                        Some(ByteSet::range(0, u8::MAX).difference(&ByteSet::range(b'0', b'9')))
+                    }
+                    _ => None,
+                };
+                let clear = stops.is_none_or(|stops| match hirs.get(i + 1) {
+                    Some(next) => {
+                        next.properties().minimum_len() != Some(0)
+                            && first_bytes(next).difference(&stops).is_empty()
+                    }
+                    None => anchor == Anchor::Whole,
+                });
+                if !clear {
+                    unclear = Some(syn::Error::new(
+                        spans[i],
+                        "Until(...) and Digits have to be followed by a pattern that \
+                         starts with a byte they stop at (or end the input), \
+                         so that it's clear where they end",
+                    ));
+                    break;
+                }
+                known.push(step);
+            }
+
+            if let Some(last) = bound {
+                if anchor == Anchor::End {
+                    return Err(syn::Error::new(
+                        span,
+                        "Seq(...) can't bind parts of the input inside Suffix(...)",
+                    ));
+                }
+                if known.len() <= last {
+                    return Err(unclear.unwrap());
+                }
+            }
+
+            let repeat = |min, max| {
+                Hir::repetition(Repetition {
+                    min,
+                    max,
+                    greedy: true,
+                    sub: Box::new(hirs[0].clone()),
+                })
+            };
+            let hir = match name.as_str() {
+                "Many" => repeat(0, None),
+                "Many1" => repeat(1, None),
+                "Opt" => repeat(0, Some(1)),
+                _ => Hir::concat(hirs),
+            };
+
+            let mut case = Case::new(hir, None, span, arm, anchor);
+            if name == "Seq" && anchor != Anchor::End {
+                case.measured = known.len() == parts;
+                case.steps = known;
+            }
+            cases.push(case);
+        }
+        Pat::TupleStruct(expr) if expr.path.is_ident("Glob") => {
+            let (glob, any) = match expr.elems.iter().collect::<Vec<_>>()[..] {
+                [Pat::Lit(PatLit {
+                    lit: Lit::Str(s), ..
+                })] => (Lit::Str(s.clone()), Dot::AnyChar),
+                [Pat::Lit(PatLit {
+                    lit: Lit::ByteStr(s),
+                    ..
+                })] => (Lit::ByteStr(s.clone()), Dot::AnyByte),
+                _ => {
+                    return Err(syn::Error::new(
+                        expr.paren_token.span.join(),
+                        "Glob(...) takes one string or byte string literal",
+                    ))
                 }
+            };
+
+            let bytes = match &glob {
+                Lit::Str(s) => s.value().into_bytes(),
+                Lit::ByteStr(s) => s.value(),
+                _ => unreachable!(),
+            };
+            let hir =
+                glob_hir(&bytes, any, ignore_case || opts.case_insensitive).ok_or_else(|| {
+                    syn::Error::new_spanned(
+                        &glob,
+                        "this glob ends in a `\\` with nothing to escape",
+                    )
+                })?;
+
+            cases.push(Case::new(hir, None, glob.span(), arm, anchor));
+        }
+        Pat::Or(expr) => {
+            for pat in expr.cases {
+                parse_arm(opts, cases, wild, pat, arm, anchor, ignore_case)?
+            }
+        }
+        Pat::Paren(expr) => parse_arm(opts, cases, wild, *expr.pat, arm, anchor, ignore_case)?,
+        Pat::Ident(PatIdent {
+            by_ref: Some(by_ref),
+            ..
+        }) => {
+            return Err(syn::Error::new_spanned(
+                by_ref,
+                "lighter! can't bind by reference; \
+                 identifier patterns bind an array of the bytes read so far",
+            ))
+        }
+        Pat::Ident(PatIdent {
+            subpat: Some((_, subpat)),
+            ..
+        }) => {
+            if segment(&subpat)?.is_some() {
+                return Err(syn::Error::new_spanned(
+                    subpat,
+                    "the bytes Until(...), Digits and Take(...) match can only be bound \
+                     inside Seq(...)",
+                ));
+            }
+            if let Pat::Ident(_) | Pat::Wild(_) = *subpat {
+                return Err(syn::Error::new_spanned(
+                    subpat,
+                    "an identifier pattern already binds the bytes read so far on its own",
+                ));
+            }
+
+            // the binding is the text of whichever case matched, so every
+            // case needs to have some
+            let (first, wilds) = (cases.len(), wild.len());
+            let span = subpat.span();
+            parse_arm(opts, cases, wild, *subpat, arm, anchor, ignore_case)?;
+            if let Some(case) = cases[first..].iter().find(|case| case.text.is_none()) {
+                return Err(syn::Error::new(
+                    case.span,
+                    "`@` can only bind patterns that stand for a single string, \
+                     not byte ranges, Any(...), Regex(...), Glob(...) or combinators like Many(...)",
+                ));
+            }
+
+            if wild.len() != wilds {
+                return Err(syn::Error::new(span, "`@` can only bind literal patterns"));
+            }
+        }
+        Pat::Ident(_) | Pat::Wild(_) => wild.push(arm),
+        pat => {
+            for lit in pat_bytes(&pat, ignore_case || opts.case_insensitive)? {
+                cases.push(Case::new(lit.hir(), lit.text, pat.span(), arm, anchor));
             }
         }
     }
 
-    None
+    Ok(())
 }
 
-fn insert_arm(expr: &mut Expr, case: &[u8], mut arm: Arm, match_prefix: bool) {
-    match case {
-        // we are at a leaf: for an n-character string, we're n matches deep,
-        // and so we have no more chars to match. iff the iterator is empty &
-        // thus the string we're matching is over, or if we are only matching
-        // a prefix, the original arm's body runs
-        [] => {
-            if match_prefix {
-                // when we are only matching a prefix, we don't care what comes
-                // after the prefix, or whether the string ends after the chars
-                // we've matched so far
-                arm.pat = parse_quote! {
-                    ::core::option::Option::Some(::core::result::Result::Ok(_)) |
-                    ::core::option::Option::None
-                };
+#[proc_macro]
+pub fn lighter(input: TokenStream) -> TokenStream {
+    expand(parse_macro_input!(input as ExprMatch), false).into()
+}
+
+// whether `pat` has a string literal in it, either on its own or inside one of
+// our own patterns, which a match on anything but a string couldn't use. A
+// pattern like `Take(3)` could just as well be a variant of the user's enum
+fn stringy(pat: &Pat) -> bool {
+    const NAMES: &[&str] = &[
+        "Prefix",
+        "Suffix",
+        "IgnoreAsciiCase",
+        "Regex",
+        "Glob",
+        "Many",
+        "Many1",
+        "Opt",
+        "Seq",
+    ];
+    match pat {
+        Pat::Lit(PatLit {
+            lit: Lit::Str(_) | Lit::ByteStr(_) | Lit::CStr(_),
+            ..
+        }) => true,
+        Pat::TupleStruct(expr) => {
+            NAMES.iter().any(|name| expr.path.is_ident(name)) && expr.elems.iter().any(stringy)
+        }
+        Pat::Or(expr) => expr.cases.iter().any(stringy),
+        Pat::Paren(expr) => stringy(&expr.pat),
+        Pat::Ident(PatIdent {
+            subpat: Some((_, subpat)),
+            ..
+        }) => stringy(subpat),
+        _ => false,
+    }
+}
+
+// whether `pat` binds the input to a name, which lighter! can only bind to
+// the bytes it read, changing what the arm sees
+fn binds_input(pat: &Pat) -> bool {
+    match pat {
+        Pat::Ident(PatIdent { subpat: None, .. }) => true,
+        Pat::Or(expr) => expr.cases.iter().any(binds_input),
+        Pat::Paren(expr) => binds_input(&expr.pat),
+        _ => false,
+    }
+}
+
+// rewrites the matches lighter! would handle, innermost first
+struct Rewrite;
+
+impl VisitMut for Rewrite {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        visit_mut::visit_expr_mut(self, expr);
+
+        if let Expr::Match(m) = expr {
+            // a #[lighter(...)] attribute is a sure sign; otherwise, every
+            // pattern has to be one we can handle, some have to be strings,
+            // and none can bind the input, which would mean something else
+            let marked = m.attrs.iter().any(|attr| attr.path().is_ident("lighter"));
+            let handled = m.attrs.is_empty()
+                && m.arms.iter().any(|arm| stringy(&arm.pat))
+                && !m.arms.iter().any(|arm| binds_input(&arm.pat))
+                && m.arms.iter().enumerate().all(|(i, arm)| {
+                    let opts = Options::default();
+                    let (mut cases, mut wild) = (Vec::new(), Vec::new());
+                    let pat = arm.pat.clone();
+                    parse_arm(&opts, &mut cases, &mut wild, pat, i, Anchor::Whole, false).is_ok()
+                });
+            if marked || handled {
+                *expr = Expr::Verbatim(expand(m.clone(), false));
+            }
+        }
+    }
+}
+
+#[proc_macro_attribute]
+pub fn rewrite(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if let Some(tt) = TokenStream2::from(attr).into_iter().next() {
+        return syn::Error::new_spanned(
+            tt,
+            "#[rewrite] doesn't take any options; put #[lighter(...)] on a match instead",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let mut item = parse_macro_input!(item as Item);
+    Rewrite.visit_item_mut(&mut item);
+    item.into_token_stream().into()
+}
+
+// the settings a #[lighter(...)] attribute on a #[derive(LighterParse)]
+// variant can change
+struct VariantOptions {
+    // the strings the variant parses from: its name (or the one it's renamed
+    // to), then any aliases
+    names: Vec<LitStr>,
+    // #[lighter(prefix = "...")]: parse the rest of the input into the fields
+    prefix: Option<LitStr>,
+    // #[lighter(separator = "...")]: what goes between the fields
+    separator: LitStr,
+    // #[lighter(other)]: parse from any string no other variant does
+    other: bool,
+}
+
+fn parse_variant_options(variant: &Variant) -> syn::Result<VariantOptions> {
+    // a raw identifier like `r#type` parses from "type"
+    let mut name = LitStr::new(&variant.ident.unraw().to_string(), variant.ident.span());
+    let mut renamed = false;
+    let mut aliases = Vec::new();
+    let mut prefix: Option<LitStr> = None;
+    let mut separator = LitStr::new(" ", Span::call_site());
+    let mut other = None;
+    for attr in &variant.attrs {
+        if !attr.path().is_ident("lighter") {
+            continue;
+        }
+
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                name = meta.value()?.parse()?;
+                renamed = true;
+            } else if meta.path.is_ident("alias") {
+                aliases.push(meta.value()?.parse()?);
+            } else if meta.path.is_ident("prefix") {
+                prefix = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("separator") {
+                separator = meta.value()?.parse()?;
+                if separator.value().is_empty() {
+                    return Err(syn::Error::new_spanned(
+                        &separator,
+                        "the separator can't be empty",
+                    ));
+                }
+            } else if meta.path.is_ident("other") {
+                other = Some(meta.path.clone());
             } else {
-                arm.pat = parse_quote!(::core::option::Option::None);
-            }
-
-            match expr {
-                // if expr is already a match statement, we can add to it as is
-                Expr::Match(m) => {
-                    // wrap arm body in Result::Ok()
-                    replace_with(
-                        arm.body.as_mut(),
-                        || parse_quote!({}), // default value only instantiated on panic
-                        |expr| parse_quote!(::core::result::Result::Ok(#expr)),
-                    );
-
-                    // we are stuck between a rock and a hard place: if the arm
-                    // is something like _ => Ok(continue), rustc will throw an
-                    // "unreachable call" warning because the Ok will never be
-                    // constructed. on the other hand, if we disable this
-                    // warning for the entire match arm, real unreachable code
-                    // warnings for the original match arm body are ignored. we
-                    // choose to ignore all unreachable code warnings because
-                    // in my experience so far that has been more ergononic for
-                    // the user versus printing many spurious errors.
-                    // TODO: when attributes can be added to expressions stably
-                    // just make the body #[allow(unreachable_code)] Ok(#expr)
-                    // https://github.com/rust-lang/rust/issues/15701
-                    arm.attrs.push(parse_quote!(#[allow(unreachable_code)]));
-
-                    m.arms.push(arm);
-                }
-
-                // if our input is some other sort of statement, make it a wild
-                // match arm that will come first and always execute, such that
-                // arms added later (including the wild arm added to all match
-                // statements by insert_wild) unreachable. this may seem silly,
-                // but the goal is to trigger an "unreachable pattern" warning
-                // when the user does something like the following:
-                // ```
-                // lighter! { match s {
-                //     Prefix("") => println!("all strings start with the empty string"),
-                //     "hi" => unreachable!(),
-                //     _ => unreachable!(),
-                // } }
-                // ```
-                expr => replace_with(
-                    expr,
-                    || parse_quote!({}), // default value only instantiated on panic
-                    |expr| {
-                        parse_quote! {
-                            match __lighter_internal_iter.next() {
-                                ::core::option::Option::Some(::core::result::Result::Err(e)) => ::core::result::Result::Err(e),
-                                _ => #expr,
-                                #[allow(unreachable_code)] #arm
-                            }
-                        }
-                    },
-                ),
+                return Err(meta.error(
+                    "unknown lighter variant option; \
+                     expected `rename`, `alias`, `prefix`, `separator` or `other`",
+                ));
             }
+
+            Ok(())
+        })?;
+    }
+
+    match &prefix {
+        Some(prefix) if renamed || !aliases.is_empty() => {
+            return Err(syn::Error::new_spanned(
+                prefix,
+                "a variant with a prefix can't also have a `rename` or an `alias`",
+            ))
+        }
+        None if !variant.fields.is_empty() => {
+            return Err(syn::Error::new_spanned(
+                &variant.fields,
+                "LighterParse needs a #[lighter(prefix = \"...\")] \
+                 to parse the fields of a variant from the rest of the input",
+            ))
         }
+        _ => {}
+    }
 
-        // we are at a leaf for a prefix match: we don't need another match
-        // statement a level after this to check iterator.next() = None, as
-        // it's OK for the iterator to have more items after this one
-        [prefix] if match_prefix => {
-            // the format! is a workaround for a bug in LitByte::value where
-            // values created with LitByte::new are not parsed correctly
-            // (TODO: report this bug)
-            let mut b = parse_str::<LitByte>(&format!("b'\\x{:02x}'", prefix)).unwrap();
-            b.set_span(arm.pat.span());
-
-            arm.pat = parse_quote!(::core::option::Option::Some(::core::result::Result::Ok(#b)));
-
-            // wrap arm body in Result::Ok()
-            replace_with(
-                arm.body.as_mut(),
-                || parse_quote!({}), // default value only instantiated on panic
-                |expr| parse_quote!(::core::result::Result::Ok(#expr)),
-            );
-
-            arm.attrs.push(parse_quote!(#[allow(unreachable_code)]));
-
-            match expr {
-                Expr::Match(m) => m.arms.push(arm),
-                expr => replace_with(
-                    expr,
-                    || parse_quote!({}), // default value only instantiated on panic
-                    |expr| {
-                        parse_quote! {
-                            match __lighter_internal_iter.next() {
-                                ::core::option::Option::Some(::core::result::Result::Err(e)) => ::core::result::Result::Err(e),
-                                _ => #expr,
-                                #arm
-                            }
-                        }
-                    },
-                ),
+    if let (Some(other), true) = (&other, prefix.is_some() || !variant.fields.is_empty()) {
+        return Err(syn::Error::new_spanned(
+            other,
+            "the `other` variant can't have a prefix or fields",
+        ));
+    }
+
+    aliases.insert(0, name);
+    Ok(VariantOptions {
+        names: aliases,
+        prefix,
+        separator,
+        other: other.is_some(),
+    })
+}
+
+// whether a type mentions any of the type parameters `params`, so it needs a
+// bound on the generated impls rather than just having the trait or not
+struct Mentions<'a> {
+    params: &'a [&'a Ident],
+    found: bool,
+}
+
+impl VisitMut for Mentions<'_> {
+    fn visit_path_mut(&mut self, path: &mut Path) {
+        if path.leading_colon.is_none()
+            && self
+                .params
+                .iter()
+                .any(|&param| path.segments[0].ident == *param)
+        {
+            self.found = true;
+        }
+        visit_mut::visit_path_mut(self, path);
+    }
+}
+
+fn derive_parse(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "LighterParse can only be derived for enums",
+        ));
+    };
+
+    // #[lighter(...)] on the enum itself goes on the generated match
+    let attrs: Vec<_> = (input.attrs.iter())
+        .filter(|attr| attr.path().is_ident("lighter"))
+        .cloned()
+        .collect();
+    let krate = lighter_path(parse_options(&attrs)?.krate);
+
+    let mut arms = Vec::new();
+    let mut strs = Vec::new();
+    let mut prints = Vec::new();
+    let mut all = Vec::new();
+    // the field types that mention a type parameter, which the impls need to
+    // be able to parse and print
+    let params: Vec<_> = input
+        .generics
+        .type_params()
+        .map(|param| &param.ident)
+        .collect();
+    let mut generic = Vec::new();
+    // whether any variant has fields, which its arm parses into a variable
+    // declared before the match
+    let mut prefixed = false;
+
+    // whatever no other variant parses from goes to the `other` variant, if
+    // there is one
+    let options = (data.variants.iter())
+        .map(parse_variant_options)
+        .collect::<syn::Result<Vec<_>>>()?;
+    let mut other = None;
+    for (variant, opts) in data.variants.iter().zip(&options) {
+        if opts.other {
+            if other.is_some() {
+                return Err(syn::Error::new_spanned(
+                    &variant.ident,
+                    "only one variant can be the `other` variant",
+                ));
             }
+            other = Some(&variant.ident);
         }
+    }
+    let unmatched = match other {
+        Some(other) => quote!(::core::result::Result::Ok(Self::#other)),
+        None => quote!(::core::result::Result::Err(#krate::ParseError)),
+    };
 
-        // there is at least one byte left to match, let's find or create
-        // another level of match statement for each next byte recursively
-        [prefix, suffix @ ..] => match expr {
-            Expr::Match(m) => {
-                let m_arm = match find_arm(m, *prefix) {
-                    // an arm already exists with our prefix byte;
-                    // insert our string's suffix relative to that
-                    Some(m_arm) => m_arm,
+    for (variant, opts) in data.variants.iter().zip(&options) {
+        for field in &variant.fields {
+            let mut mentions = Mentions {
+                params: &params,
+                found: false,
+            };
+            mentions.visit_type_mut(&mut field.ty.clone());
+            if mentions.found && !generic.contains(&field.ty) {
+                generic.push(field.ty.clone());
+            }
+        }
+        let ident = &variant.ident;
 
-                    // an arm does not yet exist for this prefix
-                    None => {
-                        // the format! is a workaround for a bug in
-                        // LitByte::value where values created with
-                        // LitByte::new are not parsed correctly
-                        let mut b = parse_str::<LitByte>(&format!("b'\\x{:02x}'", prefix)).unwrap();
-                        b.set_span(arm.pat.span());
-
-                        // TODO: parse_quote_spanned! ?
-                        m.arms.push(parse_quote! {
-                            ::core::option::Option::Some(::core::result::Result::Ok(#b)) => match __lighter_internal_iter.next() {
-                                ::core::option::Option::Some(::core::result::Result::Err(e)) => ::core::result::Result::Err(e),
-                            },
-                        });
+        // the fields, bound to names in the same order whether they have them
+        // or not, and a constructor (or pattern) made of those names
+        let binds: Vec<_> = (0..variant.fields.len())
+            .map(|i| format_ident!("__lighter_internal_{}", i))
+            .collect();
+        let fields = |by_ref: Option<Token![ref]>| match &variant.fields {
+            Fields::Named(fields) => {
+                let names = fields.named.iter().map(|field| &field.ident);
+                quote!({ #(#names: #by_ref #binds),* })
+            }
+            Fields::Unnamed(_) => quote!(( #(#by_ref #binds),* )),
+            Fields::Unit => quote!(),
+        };
 
-                        m.arms.last_mut().unwrap().body.as_mut()
-                    }
-                };
+        // the first name (or the prefix) is the one it prints as
+        let str = opts.prefix.as_ref().unwrap_or(&opts.names[0]);
+        strs.push(quote!(Self::#ident { .. } => #str));
+        if variant.fields.is_empty() {
+            prints.push(quote!(Self::#ident => f.pad(#str)));
+            // the `other` variant doesn't parse from its own name any more
+            // than from the rest, so it gets no arm or place in ALL
+            if opts.other {
+                continue;
+            }
+            all.push(quote!((#str, Self::#ident)));
+        } else {
+            let separator = &opts.separator;
+            let (first, rest) = (&binds[0], &binds[1..]);
+            let pat = fields(Some(Default::default()));
+            prints.push(quote! {
+                Self::#ident #pat => {
+                    f.write_str(#str)?;
+                    ::core::fmt::Display::fmt(#first, f)?;
+                    #(
+                        f.write_str(#separator)?;
+                        ::core::fmt::Display::fmt(#rest, f)?;
+                    )*
+                    ::core::result::Result::Ok(())
+                }
+            });
+        }
 
-                insert_arm(m_arm, suffix, arm, match_prefix);
-            }
-            expr => {
-                // the format! is a workaround for a bug in LitByte::value where
-                // values created with LitByte::new are not parsed correctly
-                // (TODO: report this bug)
-                let mut b = parse_str::<LitByte>(&format!("b'\\x{:02x}'", prefix)).unwrap();
-                b.set_span(arm.pat.span());
-
-                replace_with(
-                    expr,
-                    || parse_quote!({}), // default value only instantiated on panic
-                    |expr| {
-                        parse_quote! {
-                            match __lighter_internal_iter.next() {
-                                #[allow(unreachable_code)] _ => #expr,
-                                ::core::option::Option::Some(::core::result::Result::Ok(#b)) => match __lighter_internal_iter.next() {
-                                    ::core::option::Option::Some(::core::result::Result::Err(e)) => ::core::result::Result::Err(e),
-                                },
-                            }
-                        }
-                    },
-                );
+        arms.push(match &opts.prefix {
+            None => {
+                let names = &opts.names;
+                quote!(#(#names)|* => ::core::result::Result::Ok(Self::#ident))
             }
-        },
+            // the fields are split up at the separator, with whatever's left
+            // after the last one going in the last field. they're parsed in
+            // the guard, so if they don't parse, the arms after this one
+            // (like one with a shorter prefix) still get a try
+            Some(prefix) => {
+                let (len, separator) = (binds.len(), &opts.separator);
+                let fields = fields(None);
+                prefixed = true;
+                quote! {
+                    Prefix(#prefix, rest) if {
+                        __lighter_internal_parsed = (|| {
+                            let rest = ::core::str::from_utf8(rest).ok()?;
+                            let mut parts = rest.splitn(#len, #separator);
+                            #(let #binds = parts.next()?.parse().ok()?;)*
+                            ::core::option::Option::Some(Self::#ident #fields)
+                        })();
+                        __lighter_internal_parsed.is_some()
+                    } => __lighter_internal_parsed.ok_or(#krate::ParseError)
+                }
+            }
+        });
     }
-}
 
-// recursively append wild/fallback cases to every match expression that doesn't already have one
-// TODO: what if wild case comes earlier than the last in the match statement?
-fn insert_wild(expr: &mut Expr, wild: &[Arm], prefix: &mut Vec<u8>) {
-    if let Expr::Match(m) = expr {
-        let mut has_wild = false;
-        for arm in m.arms.iter_mut() {
-            match &arm.pat {
-                Pat::Wild(_) => {
-                    has_wild = true;
-                    insert_wild(arm.body.as_mut(), wild, prefix);
+    // two variants with the same string are an error, not just a warning
+    let parsed =
+        prefixed.then(|| quote!(let mut __lighter_internal_parsed = ::core::option::Option::None;));
+    let body = expand(
+        parse_quote! {
+            #(#attrs)*
+            match s {
+                #(#arms,)*
+                _ => #unmatched,
+            }
+        },
+        true,
+    );
+
+    // each impl gets the enum's own bounds, plus the ones it needs
+    let bounded = |bounds: Vec<syn::WherePredicate>| {
+        let mut generics = input.generics.clone();
+        generics.make_where_clause().predicates.extend(bounds);
+        generics
+    };
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let consts = bounded(vec![parse_quote!(Self: 'static)]);
+    let (_, _, consts_where) = consts.split_for_impl();
+    let display = bounded(
+        (generic.iter())
+            .map(|ty| parse_quote!(#ty: ::core::fmt::Display))
+            .collect(),
+    );
+    let (_, _, display_where) = display.split_for_impl();
+    let parse = bounded(
+        (generic.iter())
+            .map(|ty| parse_quote!(#ty: ::core::str::FromStr))
+            .collect(),
+    );
+    let (_, _, parse_where) = parse.split_for_impl();
+
+    let (vis, ident) = (&input.vis, &input.ident);
+    Ok(quote! {
+        impl #impl_generics #ident #ty_generics #consts_where {
+            /// Every variant without fields, along with the string it parses
+            /// from and prints as.
+            #vis const ALL: &'static [(&'static str, Self)] = &[#(#all),*];
+        }
+
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// The string this variant parses from and prints as, or for a
+            /// variant with fields, the prefix that comes before them.
+            #vis fn as_str(&self) -> &'static str {
+                match *self {
+                    #(#strs,)*
                 }
-                Pat::TupleStruct(expr) => {
-                    if expr.path == parse_quote!(::core::option::Option::Some)
-                        && expr.pat.elems.len() == 1
-                    {
-                        if let Some(Pat::TupleStruct(expr)) = expr.pat.elems.first() {
-                            if expr.path == parse_quote!(::core::result::Result::Ok)
-                                && expr.pat.elems.len() == 1
-                            {
-                                if let Some(Pat::Lit(expr)) = expr.pat.elems.first() {
-                                    if let Expr::Lit(expr) = expr.expr.as_ref() {
-                                        if let Lit::Byte(b) = &expr.lit {
-                                            prefix.push(b.value());
-                                            insert_wild(arm.body.as_mut(), wild, prefix);
-                                            assert_eq!(prefix.pop().unwrap(), b.value());
-                                            continue;
-                                        }
-                                    }
-                                }
-                            }
+            }
+        }
+
+        impl #impl_generics ::core::fmt::Display for #ident #ty_generics #display_where {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                match *self {
+                    #(#prints,)*
+                }
+            }
+        }
+
+        impl #impl_generics ::core::str::FromStr for #ident #ty_generics #parse_where {
+            type Err = #krate::ParseError;
+
+            fn from_str(s: &str) -> ::core::result::Result<Self, Self::Err> {
+                ::core::convert::TryFrom::try_from(s.as_bytes())
+            }
+        }
+
+        impl #impl_generics ::core::convert::TryFrom<&str> for #ident #ty_generics #parse_where {
+            type Error = #krate::ParseError;
+
+            fn try_from(s: &str) -> ::core::result::Result<Self, Self::Error> {
+                ::core::convert::TryFrom::try_from(s.as_bytes())
+            }
+        }
+
+        impl #impl_generics ::core::convert::TryFrom<&[u8]> for #ident #ty_generics #parse_where {
+            type Error = #krate::ParseError;
+
+            fn try_from(s: &[u8]) -> ::core::result::Result<Self, Self::Error> {
+                #parsed
+                #body
+            }
+        }
+    })
+}
+
+// deserializes a LighterParse enum from an identifier, like the ones serde
+// derives for the fields of a struct or the variants of an enum
+fn derive_deserialize(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "LighterDeserialize can only be derived for enums",
+        ));
+    };
+
+    let attrs: Vec<_> = (input.attrs.iter())
+        .filter(|attr| attr.path().is_ident("lighter"))
+        .cloned()
+        .collect();
+    let krate = lighter_path(parse_options(&attrs)?.krate);
+
+    let mut names = Vec::new();
+    let mut variants = Vec::new();
+    let mut other = None;
+    for variant in &data.variants {
+        if !variant.fields.is_empty() {
+            return Err(syn::Error::new_spanned(
+                &variant.fields,
+                "LighterDeserialize only supports variants without fields",
+            ));
+        }
+
+        // the `other` variant stands for every name that isn't one of these
+        let opts = parse_variant_options(variant)?;
+        if opts.other {
+            other = Some(&variant.ident);
+        } else {
+            names.push(opts.names[0].clone());
+        }
+        variants.push(&variant.ident);
+    }
+
+    // serde also accepts the index of the variant instead of its name, and
+    // like serde_derive, sends any other index to the `other` variant
+    let indices = 0..variants.len() as u64;
+    let ident = &input.ident;
+    let unknown = match other {
+        Some(other) => quote!(::core::result::Result::Ok(#ident::#other)),
+        None => {
+            let expected = format!("an index less than {}", variants.len());
+            quote! {
+                ::core::result::Result::Err(E::invalid_value(Unexpected::Unsigned(v), &#expected))
+            }
+        }
+    };
+    Ok(quote! {
+        impl<'de> #krate::__internal::serde::Deserialize<'de> for #ident {
+            fn deserialize<D>(deserializer: D) -> ::core::result::Result<Self, D::Error>
+            where
+                D: #krate::__internal::serde::Deserializer<'de>,
+            {
+                use #krate::__internal::serde::de::{Error, Unexpected, Visitor};
+
+                const NAMES: &[&str] = &[#(#names),*];
+
+                struct IdentVisitor;
+
+                impl<'de> Visitor<'de> for IdentVisitor {
+                    type Value = #ident;
+
+                    fn expecting(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                        f.write_str("an identifier")
+                    }
+
+                    fn visit_u64<E: Error>(self, v: u64) -> ::core::result::Result<#ident, E> {
+                        match v {
+                            #(#indices => ::core::result::Result::Ok(#ident::#variants),)*
+                            _ => #unknown,
                         }
                     }
-                    // TODO println!("non-Some(Ok) TupleStruct: {}", quote!(#expr).to_string());
-                }
-                p => panic!("weird pat when adding wild arms: {:?}", p),
-            }
-        }
-
-        if !has_wild {
-            for arm in wild {
-                match &arm.pat {
-                    Pat::Wild(_) => {
-                        // non-dotted name for quote!
-                        let body = &arm.body;
-
-                        m.arms.push(Arm {
-                            attrs: arm
-                                .attrs
-                                .iter()
-                                .cloned()
-                                .chain(iter::once(parse_quote!(#[allow(unreachable_code)])))
-                                .collect(),
-                            pat: arm.pat.clone(),
-                            guard: arm.guard.clone(),
-                            fat_arrow_token: arm.fat_arrow_token,
-                            body: Box::new(parse_quote!(::core::result::Result::Ok(#body))),
-                            comma: arm.comma,
-                        });
+
+                    fn visit_str<E: Error>(self, v: &str) -> ::core::result::Result<#ident, E> {
+                        ::core::convert::TryFrom::try_from(v.as_bytes())
+                            .map_err(|_| E::unknown_variant(v, NAMES))
                     }
-                    Pat::Ident(PatIdent {
-                        attrs,
-                        by_ref,
-                        mutability,
-                        ident,
-                        subpat,
-                    }) => {
-                        assert!(by_ref.is_none());
-                        assert!(subpat.is_none());
-
-                        // we need to handle two cases: one where we *did* read
-                        // another byte (i.e. the iterator returned Some(b) and
-                        // one where the iterator didn't read another byte (it
-                        // returned None), but the wild case should still run
-                        // with any previously read bytes (those in `prefix`)
-
-                        // TODO: maybe this sort of wild arm should be added as
-                        // we go along instead of after all the others, so that
-                        // the following code would yield an "unreachable expr"
-                        // error for the second match arm:
-                        // ```
-                        // match b {
-                        //     s => println!("matched wild {}", s);
-                        //     Prefix('x') => println!("matched x");
-                        // }
-                        // ```
-
-                        // make a short name for arm.body, because quote! would
-                        // expand #arm.body as (#arm).body, not #(arm.body)
-                        let body = &arm.body;
-
-                        // add match arm for the Some(b) case
-                        {
-                            let len = prefix.len() + 1;
-                            let bytes = prefix
-                                .iter()
-                                .map(|b| quote!(#b))
-                                .chain(iter::once(quote!(__lighter_internal_last_byte)));
-
-                            m.arms.push(Arm {
-                                attrs: arm
-                                    .attrs
-                                    .iter()
-                                    .cloned()
-                                    .chain(iter::once(parse_quote!(#[allow(unreachable_code)])))
-                                    .collect(),
-                                pat: parse_quote!(::core::option::Option::Some(
-                                    ::core::result::Result::Ok(__lighter_internal_last_byte)
-                                )),
-                                guard: arm.guard.clone(),
-                                fat_arrow_token: arm.fat_arrow_token,
-                                body: Box::new(parse_quote! {
-                                    {
-                                        let #mutability #ident: [u8; #len] = [#(#bytes),*];
-                                        ::core::result::Result::Ok(#body)
-                                    }
-                                }),
-                                comma: arm.comma,
-                            });
-                        }
 
-                        // add match arm for the None case
-                        {
-                            let len = prefix.len();
-                            let bytes = prefix.iter().map(|b| quote!(#b));
-
-                            m.arms.push(Arm {
-                                attrs: arm
-                                    .attrs
-                                    .iter()
-                                    .cloned()
-                                    .chain(iter::once(parse_quote!(#[allow(unreachable_code)])))
-                                    .collect(),
-                                pat: parse_quote!(::core::option::Option::None),
-                                guard: arm.guard.clone(),
-                                fat_arrow_token: arm.fat_arrow_token,
-                                body: Box::new(parse_quote! {
-                                    {
-                                        let #mutability #ident: [u8; #len] = [#(#bytes),*];
-                                        ::core::result::Result::Ok(#body)
-                                    }
-                                }),
-                                comma: arm.comma,
-                            });
-                        }
+                    fn visit_bytes<E: Error>(self, v: &[u8]) -> ::core::result::Result<#ident, E> {
+                        ::core::convert::TryFrom::try_from(v).map_err(|_| {
+                            match ::core::str::from_utf8(v) {
+                                ::core::result::Result::Ok(v) => E::unknown_variant(v, NAMES),
+                                ::core::result::Result::Err(_) => {
+                                    E::invalid_value(Unexpected::Bytes(v), &self)
+                                }
+                            }
+                        })
                     }
-                    _ => todo!(),
                 }
+
+                deserializer.deserialize_identifier(IdentVisitor)
             }
         }
-    }
+    })
 }
 
-// TODO: error handling
-// TODO: assert no attrs etc.
-fn parse_arm(match_out: &mut Expr, wild: &mut Vec<Arm>, arm: Arm, prefix: bool) {
-    match arm.pat {
-        Pat::Lit(ref expr) => match expr.expr.as_ref() {
-            Expr::Lit(expr) => match &expr.lit {
-                Lit::Str(expr) => insert_arm(match_out, expr.value().as_bytes(), arm, prefix),
-                Lit::Byte(expr) => insert_arm(match_out, &[expr.value()], arm, prefix),
-                Lit::Char(expr) => {
-                    let mut buf = [0; 4];
-                    let c = expr.value().encode_utf8(&mut buf).as_bytes();
-                    insert_arm(match_out, c, arm, prefix)
-                }
-                // TODO: handle if guards
-                _ => todo!("non-str lit"),
-            },
-            _ => todo!("non-lit expr"),
-        },
-        Pat::TupleStruct(expr)
-            if expr.path == parse_quote!(Prefix) && expr.pat.elems.len() == 1 =>
-        {
-            let arm = Arm {
-                pat: expr.pat.elems.into_iter().next().unwrap(),
-                ..arm
-            };
+#[proc_macro_derive(LighterDeserialize, attributes(lighter))]
+pub fn derive_lighter_deserialize(input: TokenStream) -> TokenStream {
+    derive_deserialize(parse_macro_input!(input as DeriveInput))
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
 
-            parse_arm(match_out, wild, arm, true)
-        }
-        Pat::Or(expr) => {
-            //for pat in &expr.cases {
-            for pat in expr.cases {
-                parse_arm(
-                    match_out,
-                    wild,
-                    Arm {
-                        attrs: arm.attrs.clone(),
-                        pat,
-                        guard: arm.guard.clone(),
-                        fat_arrow_token: arm.fat_arrow_token,
-                        body: arm.body.clone(),
-                        comma: arm.comma,
-                    },
-                    prefix,
-                )
-            }
+#[proc_macro_derive(LighterParse, attributes(lighter))]
+pub fn derive_lighter_parse(input: TokenStream) -> TokenStream {
+    derive_parse(parse_macro_input!(input as DeriveInput))
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+// the path to the lighter crate, if not given with #[lighter(crate = path)]
+fn lighter_path(krate: Option<Path>) -> Path {
+    match (krate, crate_name("lighter")) {
+        (Some(krate), _) => krate,
+        (None, Ok(FoundCrate::Name(name))) => {
+            let name = Ident::new(&name, Span::call_site());
+            parse_quote!(::#name)
         }
-        Pat::Ident(_) | Pat::Wild(_) => wild.push(arm),
-        x => todo!("non-lit pat {:?}", x),
-        //_ => todo!("non-lit pat"),
+        _ => parse_quote!(::lighter),
     }
 }
 
-#[proc_macro]
-pub fn lighter(input: TokenStream) -> TokenStream {
+// `deny_shadowed` makes a pattern an earlier arm always matches first an error
+// instead of a warning
+fn expand(input: ExprMatch, deny_shadowed: bool) -> TokenStream2 {
     let ExprMatch {
         attrs,
         match_token,
         expr,
         brace_token,
         arms,
-    } = parse_macro_input!(input as ExprMatch);
-    if !attrs.is_empty() {
-        panic!("I don't know what to do with attributes on a match statement");
+    } = input;
+    let opts = match parse_options(&attrs) {
+        Ok(opts) => opts,
+        Err(e) => return e.to_compile_error(),
+    };
+
+    // report every arm we can't handle at once, rather than just the first
+    let mut cases = Vec::new();
+    let mut wild: Vec<usize> = Vec::new();
+    let mut errors: Option<syn::Error> = None;
+    let mut error = |e| match &mut errors {
+        Some(errors) => syn::Error::combine(errors, e),
+        None => errors = Some(e),
+    };
+    for (i, arm) in arms.iter().enumerate() {
+        if let Err(e) = parse_arm(
+            &opts,
+            &mut cases,
+            &mut wild,
+            arm.pat.clone(),
+            i,
+            Anchor::Whole,
+            false,
+        ) {
+            error(e);
+        }
     }
 
-    let mut wild = Vec::new();
-    // TODO: lighter! { match { Prefix("") => {} } } should consume 0 bytes/do nothing
-    let mut match_out = Expr::Match(ExprMatch {
-        attrs,
-        match_token,
-        expr: parse_quote_spanned!(expr.span()=> __lighter_internal_iter.next()),
-        brace_token,
-        arms: vec![parse_quote! {
-            ::core::option::Option::Some(::core::result::Result::Err(e)) => ::core::result::Result::Err(e),
-        }],
+    // like in a normal match statement, every alternative of an arm has to
+    // bind the same names, or the body would refer to one that doesn't exist
+    for (i, arm) in arms.iter().enumerate() {
+        let rest = |case: &Case| case.rest.as_ref().map(|rest| rest.ident.clone());
+        let mut arm_cases = cases.iter().filter(|case| case.arm == i);
+        if let Some(first) = arm_cases.next() {
+            let name = rest(first);
+            if let Some(case) = arm_cases.find(|case| rest(case) != name) {
+                error(syn::Error::new(
+                    case.span,
+                    "every alternative of this arm has to bind the same rest of the input",
+                ));
+            } else if name.is_some() && wild.contains(&i) {
+                error(syn::Error::new_spanned(
+                    &arm.pat,
+                    "a `_` or identifier alternative can't bind the rest of the input",
+                ));
+            }
+        }
+
+        // the table doesn't keep the bytes it read
+        if let (Strategy::Table, Pat::Ident(PatIdent { subpat: None, .. })) =
+            (opts.strategy, &arm.pat)
+        {
+            if wild.contains(&i) {
+                error(syn::Error::new_spanned(
+                    &arm.pat,
+                    "an identifier pattern can't bind the input with strategy = \"table\"; \
+                     use `_` instead",
+                ));
+            }
+        }
+
+        let mut arm_cases = cases.iter().filter(|case| case.arm == i);
+        let binds = arm_cases.clone().any(Case::binds);
+        if binds && (arm_cases.nth(1).is_some() || wild.contains(&i)) {
+            error(syn::Error::new_spanned(
+                &arm.pat,
+                "an arm that binds parts of a Seq(...) can't have other alternatives",
+            ));
+        }
+    }
+
+    // like in a normal match statement, no arm after an unguarded `_` or
+    // identifier arm can ever run, so there's no point putting it in the DFA.
+    // for longest-match, any arm that matches some input beats it
+    let longest = opts.prefer == Prefer::Longest;
+    let reachable = |arm: usize| {
+        (longest && !wild.contains(&arm))
+            || wild.iter().all(|&w| arm <= w || arms[w].guard.is_some())
+    };
+
+    // Suffix arms need the DFA to be built from the end of the input
+    // backwards, which works just as well for whole-string patterns but
+    // means we can't read from the start of the input for Prefix arms
+    let reverse = cases.iter().any(|case| case.anchor == Anchor::End);
+    if reverse {
+        for case in cases.iter().filter(|case| case.anchor == Anchor::Start) {
+            error(syn::Error::new(
+                case.span,
+                "Prefix(...) can't be used in the same lighter! as Suffix(...)",
+            ));
+        }
+    }
+
+    // the NFA for the cases `keep` picks, and the wild arms `wild`
+    let nfa_for = |keep: &dyn Fn(&Case) -> bool, wild: &[usize]| {
+        let mut nfa = Nfa {
+            states: Vec::new(),
+            reverse,
+            longest,
+        };
+        let start = nfa.add();
+        for (i, case) in cases.iter().enumerate() {
+            if !reachable(case.arm) || (reverse && case.anchor == Anchor::Start) || !keep(case) {
+                continue;
+            }
+
+            let accept = Accept {
+                arm: case.arm,
+                case: Some(i),
+            };
+            let end = nfa.compile(&case.hir, start, case.span);
+            nfa.accept(end, accept, case.anchor != Anchor::Whole);
+        }
+
+        // a wild arm matches no matter what we read, just like a Prefix("") arm
+        for &arm in wild {
+            nfa.accept(start, Accept { arm, case: None }, true);
+        }
+
+        (nfa, start)
+    };
+    let wild_reachable: Vec<usize> = wild.iter().copied().filter(|&arm| reachable(arm)).collect();
+    let (nfa, start) = nfa_for(&|_| true, &wild_reachable);
+
+    let states = build_dfa(&nfa, start).unwrap_or_else(|e| {
+        error(e);
+        Vec::new()
     });
 
-    for arm in arms {
-        parse_arm(&mut match_out, &mut wild, arm, false);
+    if let Some(errors) = errors {
+        // each error is its own compile_error! invocation, so we need a
+        // block around them to keep the macro output a single expression
+        let errors = errors.to_compile_error();
+        return quote!({ #errors });
+    }
+
+    // the nested matches are only exhaustive if some unguarded arm always
+    // matches, and rustc would complain about them instead of the input
+    if let Some(mut input) = unmatched(&states, &arms) {
+        if reverse {
+            input.reverse();
+        }
+        let input = match std::str::from_utf8(&input) {
+            Ok(s) if !s.chars().any(char::is_control) => format!("{:?}", s),
+            _ => format!("b\"{}\"", input.escape_ascii()),
+        };
+        let error = syn::Error::new(
+            brace_token.span.close(),
+            format!(
+                "this lighter! doesn't match every input (e.g. {}); \
+                 add a `_` arm for the input no other arm matches",
+                input
+            ),
+        );
+        let error = error.to_compile_error();
+        return quote!({ #error });
+    }
+
+    let krate = lighter_path(opts.krate.clone());
+    let classes = nfa.classes();
+    let ctx = Context {
+        arms: &arms,
+        cases: &cases,
+        states: &states,
+        classes: &classes,
+        opts: &opts,
+        reverse,
+        word_end: None,
+    };
+    let mut match_out = match ctx.opts.strategy {
+        Strategy::Nested => make_match(&ctx, 0, Some(&mut Vec::new()), &[]),
+        Strategy::Table => Expr::Match(make_table(&ctx)),
+        Strategy::Length => {
+            // the lengths some case always matches exactly. a DFA for one
+            // of them only needs those cases and the ones that can match any
+            // length, and the one for every other length only the latter
+            let mut lens: Vec<usize> = cases
+                .iter()
+                .filter(|case| reachable(case.arm) && case.anchor == Anchor::Whole)
+                .filter_map(|case| case.len)
+                .collect();
+            lens.sort_unstable();
+            lens.dedup();
+
+            // without some of the cases, the DFA only handles every input of
+            // the right length, so the other ones go to an extra wild arm
+            let mut len_arms = arms.clone();
+            len_arms.push(parse_quote!(_ => ::core::unreachable!()));
+            let mut len_wild = wild_reachable.clone();
+            len_wild.push(arms.len());
+
+            let make_len = |len: Option<usize>| {
+                let keep = |case: &Case| {
+                    case.anchor != Anchor::Whole || case.len.is_none() || case.len == len
+                };
+                let (nfa, start) = nfa_for(&keep, &len_wild);
+                let states = match build_dfa(&nfa, start) {
+                    Ok(states) => states,
+                    Err(e) => return e.to_compile_error(),
+                };
+                let classes = nfa.classes();
+                let ctx = Context {
+                    arms: &len_arms,
+                    cases: &cases,
+                    states: &states,
+                    classes: &classes,
+                    opts: &opts,
+                    reverse,
+                    word_end: None,
+                };
+                make_match(&ctx, 0, Some(&mut Vec::new()), &[]).into_token_stream()
+            };
+            let exprs: Vec<_> = lens.iter().map(|&len| make_len(Some(len))).collect();
+            let other = make_len(None);
+
+            // as with insert_wild, a body that never finishes would make
+            // rustc warn about the Ok around it
+            let span = Span::call_site().located_at(expr.span());
+            let len = quote_spanned!(span=> #krate::__internal::KnownLen::known_len(&__lighter_internal_iter));
+            parse_quote! {
+                match #len {
+                    #(
+                        #[allow(unreachable_code)]
+                        #lens => #exprs,
+                    )*
+                    #[allow(unreachable_code)]
+                    _ => #other,
+                }
+            }
+        }
+    };
+    match &mut match_out {
+        Expr::Match(m) => {
+            // if the input's iterator isn't a DoubleEndedIterator, the error
+            // about next_back() not existing should point at the input. the
+            // name still has to resolve where we defined it, which isn't where
+            // the input came from if lighter! is inside a macro_rules!. a
+            // match on whether a word was there already has its own
+            if ctx.opts.strategy == Strategy::Nested && matches!(*m.expr, Expr::MethodCall(_)) {
+                let span = Span::call_site().located_at(expr.span());
+                let next = next_byte(reverse, span);
+                m.expr = parse_quote_spanned!(span=> __lighter_internal_iter.#next());
+            }
+            m.match_token = match_token;
+            m.brace_token = brace_token;
+        }
+
+        // an arm (e.g. Prefix("")) could run without reading anything, so
+        // the arms we would otherwise add the attribute to are missing
+        expr => replace_with(
+            expr,
+            || parse_quote!({}), // default value only instantiated on panic
+            |expr| {
+                parse_quote! {
+                    match () {
+                        #[allow(unreachable_code)]
+                        () => #expr,
+                    }
+                }
+            },
+        ),
     }
 
-    insert_wild(&mut match_out, &wild, &mut Vec::new());
+    // a pattern that some earlier arm always matches first gets a warning
+    // at the pattern the user wrote, naming the arm in the way
+    let mut warnings = Vec::new();
+    let shadowed = |span: Span, by: &Accept, arm: usize| {
+        if deny_shadowed {
+            let pat = arms[by.arm].pat.to_token_stream();
+            let msg = format!("`{}` already matches this string", pat);
+            return syn::Error::new(span, msg).to_compile_error();
+        }
 
-    let krate = match crate_name("lighter") {
-        Ok(FoundCrate::Name(name)) => Ident::new(&name, Span::call_site()),
-        _ => parse_quote!(lighter),
+        let msg = if by.arm == arm {
+            "this pattern is unreachable: \
+             an earlier alternative of the same arm matches everything it does"
+                .to_string()
+        } else {
+            let pat = arms[by.arm].pat.to_token_stream();
+            format!(
+                "this pattern is unreachable: `{}` on an earlier arm matches everything it does",
+                pat
+            )
+        };
+        warning(span, &msg)
     };
+    for (i, case) in cases.iter().enumerate() {
+        let accept = Accept {
+            arm: case.arm,
+            case: Some(i),
+        };
+
+        // the case runs if, in some state, it comes before any other case of
+        // its arm and any unguarded arm, whether the input ends there or not
+        let mut by = None;
+        let mut runs = false;
+        for list in states.iter().flat_map(|state| [&state.end, &state.sticky]) {
+            if let Some(pos) = list.iter().position(|other| *other == accept) {
+                match list[..pos]
+                    .iter()
+                    .find(|other| other.arm == case.arm || arms[other.arm].guard.is_none())
+                {
+                    Some(other) => by = by.or(Some(*other)),
+                    None => runs = true,
+                }
+            }
+        }
+
+        if let (false, Some(by)) = (runs, by) {
+            warnings.push(shadowed(case.span, &by, case.arm));
+        }
+    }
+
+    // the unreachable arms still go in the output, behind a `_` pattern that
+    // can never match, so rustc type checks them. we warn about them at the
+    // pattern the user wrote instead of rustc, which doesn't know why
+    if let Expr::Match(m) = &mut match_out {
+        for (i, arm) in arms.iter().enumerate().filter(|&(i, _)| !reachable(i)) {
+            let accept = Accept {
+                arm: i,
+                case: cases.iter().position(|case| case.arm == i),
+            };
+            let pat = parse_quote_spanned!(arm.pat.span()=> _);
+            let len = m.arms.len();
+            insert_wild(&ctx, m, &pat, [accept], Some(&[]), false);
+            for arm in &mut m.arms[len..] {
+                arm.attrs.push(parse_quote!(#[allow(unreachable_patterns)]));
+            }
+
+            let by = wild
+                .iter()
+                .find(|&&w| w < i && arms[w].guard.is_none())
+                .expect("an arm is only unreachable after an unguarded wild arm");
+            let by = Accept {
+                arm: *by,
+                case: None,
+            };
+            warnings.push(shadowed(arm.pat.span(), &by, i));
+        }
+    }
 
-    // TODO
-    let make_iter = quote_spanned! {expr.span()=>
-        //(&mut &mut &mut ::#krate::__internal::Wrap(Some(#expr))).bytes()
-        (&mut ::#krate::__internal::Wrap(::core::option::Option::Some(#expr))).bytes()
+    let mut make_iter = quote_spanned! {expr.span()=>
+        (&mut &mut #krate::__internal::Wrap(::core::option::Option::Some(#expr))).bytes()
     };
+    if cases.iter().any(Case::walks) {
+        make_iter = quote_spanned! {expr.span()=>
+            #krate::__internal::Record::record(#make_iter)
+        };
+    }
 
-    TokenStream::from(quote! {
+    quote! {
         {
-            use ::#krate::__internal::*;
+            #(#warnings)*
+            use #krate::__internal::*;
             let mut __lighter_internal_iter = #make_iter;
-            (&mut &mut ::#krate::__internal::Wrap(::core::option::Option::Some(#match_out))).maybe_unwrap()
+            (&mut &mut #krate::__internal::Wrap(::core::option::Option::Some(#match_out))).maybe_unwrap()
         }
-    })
+    }
 }
 
 /*
@@ -1,5 +1,7 @@
 use core::convert::Infallible;
 pub use lighter_derive::lighter;
+#[cfg(feature = "serde")]
+pub use serde;
 
 #[repr(transparent)]
 pub struct Wrap<T>(pub Option<T>);
@@ -14,26 +16,219 @@ impl<T> Wrap<T> {
     }
 }
 
+// the iterators that Wrap(T).bytes() returns. they all yield Result<u8, E>, so
+// the generated code can handle fallible and infallible input the same way
+
+// an Iterator<Item = u8>
+pub struct Bytes<I>(I);
+
+impl<I: Iterator<Item = u8>> Iterator for Bytes<I> {
+    type Item = Result<u8, Infallible>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(Ok)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<I: DoubleEndedIterator<Item = u8>> DoubleEndedIterator for Bytes<I> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(Ok)
+    }
+}
+
+// an Iterator<Item = &u8>
+pub struct RefBytes<I>(I);
+
+impl<'a, I: Iterator<Item = &'a u8>> Iterator for RefBytes<I> {
+    type Item = Result<u8, Infallible>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().copied().map(Ok)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a, I: DoubleEndedIterator<Item = &'a u8>> DoubleEndedIterator for RefBytes<I> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().copied().map(Ok)
+    }
+}
+
+// an Iterator<Item = Result<u8, E>>
+pub struct TryBytes<I>(I);
+
+impl<E, I: Iterator<Item = Result<u8, E>>> Iterator for TryBytes<I> {
+    type Item = Result<u8, E>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<E, I: DoubleEndedIterator<Item = Result<u8, E>>> DoubleEndedIterator for TryBytes<I> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back()
+    }
+}
+
+// an Iterator<Item = Result<&u8, E>>
+pub struct TryRefBytes<I>(I);
+
+impl<'a, E, I: Iterator<Item = Result<&'a u8, E>>> Iterator for TryRefBytes<I> {
+    type Item = Result<u8, E>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(Result::<&u8, E>::copied)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a, E, I: DoubleEndedIterator<Item = Result<&'a u8, E>>> DoubleEndedIterator
+    for TryRefBytes<I>
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(Result::<&u8, E>::copied)
+    }
+}
+
+// a &[u8], which we keep around to slice the remainder out of
+pub struct SliceBytes<'a> {
+    slice: &'a [u8],
+    iter: core::slice::Iter<'a, u8>,
+}
+
+impl<'a> SliceBytes<'a> {
+    #[inline]
+    fn new(slice: &'a [u8]) -> Self {
+        Self {
+            slice,
+            iter: slice.iter(),
+        }
+    }
+}
+
+impl Iterator for SliceBytes<'_> {
+    type Item = Result<u8, Infallible>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().copied().map(Ok)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for SliceBytes<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().copied().map(Ok)
+    }
+}
+
+// a &str, which we keep around to slice the remainder out of
+pub struct StrBytes<'a> {
+    s: &'a str,
+    iter: core::slice::Iter<'a, u8>,
+}
+
+impl Iterator for StrBytes<'_> {
+    type Item = Result<u8, Infallible>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().copied().map(Ok)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for StrBytes<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().copied().map(Ok)
+    }
+}
+
 // Wrap(T).bytes() always calls the "best" implementation to convert to Iterator<Item = u8>
 // https://lukaskalbertodt.github.io/2019/12/05/generalized-autoref-based-specialization.html
+// the macro calls (&mut &mut Wrap(Some(x))).bytes(), so the impls for
+// &mut Wrap<T> are tried first, then &mut &mut Wrap<T>, then Wrap<T>
+pub trait MatchSlice<'a> {
+    fn bytes(&mut self) -> SliceBytes<'a>;
+}
+
+impl<'a> MatchSlice<'a> for &mut Wrap<&'a [u8]> {
+    #[inline]
+    fn bytes(&mut self) -> SliceBytes<'a> {
+        SliceBytes::new(self.inner())
+    }
+}
+
+impl<'a, const N: usize> MatchSlice<'a> for &mut Wrap<&'a [u8; N]> {
+    #[inline]
+    fn bytes(&mut self) -> SliceBytes<'a> {
+        SliceBytes::new(self.inner())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> MatchSlice<'a> for &mut Wrap<&'a Vec<u8>> {
+    #[inline]
+    fn bytes(&mut self) -> SliceBytes<'a> {
+        SliceBytes::new(self.inner())
+    }
+}
+
 pub trait MatchIterator<E> {
     type Iter: Iterator<Item = Result<u8, E>>;
     fn bytes(&mut self) -> Self::Iter;
 }
 
 impl<T: IntoIterator<Item = u8>> MatchIterator<Infallible> for Wrap<T> {
-    type Iter = core::iter::Map<T::IntoIter, fn(u8) -> Result<u8, Infallible>>;
+    type Iter = Bytes<T::IntoIter>;
     #[inline]
     fn bytes(&mut self) -> Self::Iter {
-        self.inner().into_iter().map(Result::Ok)
+        Bytes(self.inner().into_iter())
     }
 }
 
-impl<E, T: IntoIterator<Item = Result<u8, E>>> MatchIterator<E> for &mut Wrap<T> {
-    type Iter = T::IntoIter;
+impl<E, T: IntoIterator<Item = Result<u8, E>>> MatchIterator<E> for &mut &mut Wrap<T> {
+    type Iter = TryBytes<T::IntoIter>;
     #[inline]
     fn bytes(&mut self) -> Self::Iter {
-        self.inner().into_iter()
+        TryBytes(self.inner().into_iter())
     }
 }
 
@@ -43,18 +238,18 @@ pub trait MatchRefIterator<E> {
 }
 
 impl<'a, T: IntoIterator<Item = &'a u8>> MatchRefIterator<Infallible> for Wrap<T> {
-    type Iter = core::iter::Map<core::iter::Copied<T::IntoIter>, fn(u8) -> Result<u8, Infallible>>;
+    type Iter = RefBytes<T::IntoIter>;
     #[inline]
     fn bytes(&mut self) -> Self::Iter {
-        self.inner().into_iter().copied().map(Result::Ok)
+        RefBytes(self.inner().into_iter())
     }
 }
 
-impl<'a, E, T: IntoIterator<Item = Result<&'a u8, E>>> MatchRefIterator<E> for &mut Wrap<T> {
-    type Iter = core::iter::Map<T::IntoIter, fn(Result<&'a u8, E>) -> Result<u8, E>>;
+impl<'a, E, T: IntoIterator<Item = Result<&'a u8, E>>> MatchRefIterator<E> for &mut &mut Wrap<T> {
+    type Iter = TryRefBytes<T::IntoIter>;
     #[inline]
     fn bytes(&mut self) -> Self::Iter {
-        self.inner().into_iter().map(Result::<&u8, E>::copied)
+        TryRefBytes(self.inner().into_iter())
     }
 }
 
@@ -64,19 +259,527 @@ pub trait MatchStr<'a> {
 }
 
 impl<'a> MatchStr<'a> for Wrap<&'a str> {
-    type Iter = core::iter::Map<core::str::Bytes<'a>, fn(u8) -> Result<u8, Infallible>>;
+    type Iter = StrBytes<'a>;
     #[inline]
     fn bytes(&mut self) -> Self::Iter {
-        self.inner().bytes().map(Result::Ok)
+        let s = self.inner();
+        StrBytes {
+            s,
+            iter: s.as_bytes().iter(),
+        }
     }
 }
 
 #[cfg(feature = "std")]
 impl<'a> MatchStr<'a> for Wrap<String> {
-    type Iter = core::iter::Map<std::vec::IntoIter<u8>, fn(u8) -> Result<u8, Infallible>>;
+    type Iter = Bytes<std::vec::IntoIter<u8>>;
     #[inline]
     fn bytes(&mut self) -> Self::Iter {
-        self.inner().into_bytes().into_iter().map(Result::Ok)
+        Bytes(self.inner().into_bytes().into_iter())
+    }
+}
+
+// what a Prefix(..., rest) arm binds `rest` to: the rest of the input after
+// the prefix (or before the suffix, if `back` is set) of `len` bytes. slices
+// and strs can give exactly that, and other iterators are recorded so they can
+// give back the bytes they read past the prefix. a str can only give the rest
+// as a str if it starts on a char boundary, which is only sure to be the case
+// if the pattern is `ALIGNED`
+#[diagnostic::on_unimplemented(
+    message = "the rest of this input could start in the middle of a UTF-8 character, \
+               so it can't be bound as a `&str`",
+    label = "this could start partway through a character",
+    note = "match on `.as_bytes()` to bind the rest as a `&[u8]` instead"
+)]
+pub trait Remainder<const ALIGNED: bool> {
+    type Rest<'b>
+    where
+        Self: 'b;
+    fn remainder(&mut self, len: usize, back: bool) -> Self::Rest<'_>;
+}
+
+impl<'a, const ALIGNED: bool> Remainder<ALIGNED> for SliceBytes<'a> {
+    type Rest<'b>
+        = &'a [u8]
+    where
+        Self: 'b;
+    #[inline(always)]
+    fn remainder(&mut self, len: usize, back: bool) -> Self::Rest<'_> {
+        if back {
+            &self.slice[..self.slice.len() - len]
+        } else {
+            &self.slice[len..]
+        }
+    }
+}
+
+impl<'a> Remainder<true> for StrBytes<'a> {
+    type Rest<'b>
+        = &'a str
+    where
+        Self: 'b;
+    #[inline(always)]
+    fn remainder(&mut self, len: usize, back: bool) -> Self::Rest<'_> {
+        if back {
+            &self.s[..self.s.len() - len]
+        } else {
+            &self.s[len..]
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<I: Source, const ALIGNED: bool> Remainder<ALIGNED> for Recorded<I> {
+    type Rest<'b>
+        = Rest<'b, I::Iter>
+    where
+        Self: 'b;
+    #[inline(always)]
+    fn remainder(&mut self, len: usize, back: bool) -> Self::Rest<'_> {
+        Rest {
+            read: self.buf[len..].iter(),
+            iter: self.iter.source(),
+            back,
+        }
+    }
+}
+
+// the iterator that Bytes, RefBytes, TryBytes and TryRefBytes read from, which
+// has what's left of the input after the bytes they read
+pub trait Source {
+    type Iter: Iterator;
+    fn source(&mut self) -> &mut Self::Iter;
+}
+
+impl<I: Iterator> Source for Bytes<I> {
+    type Iter = I;
+    #[inline(always)]
+    fn source(&mut self) -> &mut I {
+        &mut self.0
+    }
+}
+
+impl<I: Iterator> Source for RefBytes<I> {
+    type Iter = I;
+    #[inline(always)]
+    fn source(&mut self) -> &mut I {
+        &mut self.0
+    }
+}
+
+impl<I: Iterator> Source for TryBytes<I> {
+    type Iter = I;
+    #[inline(always)]
+    fn source(&mut self) -> &mut I {
+        &mut self.0
+    }
+}
+
+impl<I: Iterator> Source for TryRefBytes<I> {
+    type Iter = I;
+    #[inline(always)]
+    fn source(&mut self) -> &mut I {
+        &mut self.0
+    }
+}
+
+// an item of one of the iterators we read from, made back out of a byte
+pub trait FromByte {
+    fn from_byte(byte: u8) -> Self;
+}
+
+// every byte, so that a &u8 for any of them can live as long as it has to
+static BYTES: [u8; 256] = {
+    let mut bytes = [0; 256];
+    let mut i = 0;
+    while i < bytes.len() {
+        bytes[i] = i as u8;
+        i += 1;
+    }
+    bytes
+};
+
+impl FromByte for u8 {
+    #[inline(always)]
+    fn from_byte(byte: u8) -> Self {
+        byte
+    }
+}
+
+impl FromByte for &u8 {
+    #[inline(always)]
+    fn from_byte(byte: u8) -> Self {
+        &BYTES[byte as usize]
+    }
+}
+
+impl<T: FromByte, E> FromByte for Result<T, E> {
+    #[inline(always)]
+    fn from_byte(byte: u8) -> Self {
+        Ok(T::from_byte(byte))
+    }
+}
+
+// the rest of an iterator's input: the bytes we read past the prefix, then
+// whatever the iterator has left. for a suffix, the bytes we read before it
+// were read from the back, so they come after the iterator's, in reverse
+pub struct Rest<'b, I> {
+    read: core::slice::Iter<'b, u8>,
+    iter: &'b mut I,
+    back: bool,
+}
+
+impl<I: Iterator<Item: FromByte>> Iterator for Rest<'_, I> {
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.back {
+            let read = &mut self.read;
+            (self.iter.next()).or_else(|| read.next_back().map(|&b| FromByte::from_byte(b)))
+        } else {
+            let read = self.read.next().map(|&b| FromByte::from_byte(b));
+            read.or_else(|| self.iter.next())
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lo, hi) = self.iter.size_hint();
+        let read = self.read.len();
+        (
+            lo.saturating_add(read),
+            hi.and_then(|hi| hi.checked_add(read)),
+        )
+    }
+}
+
+impl<I: DoubleEndedIterator<Item: FromByte>> DoubleEndedIterator for Rest<'_, I> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.back {
+            let read = self.read.next().map(|&b| FromByte::from_byte(b));
+            read.or_else(|| self.iter.next_back())
+        } else {
+            let read = &mut self.read;
+            (self.iter.next_back()).or_else(|| read.next_back().map(|&b| FromByte::from_byte(b)))
+        }
+    }
+}
+
+// a fallible or infallible iterator that keeps the bytes it reads, so that
+// Seq(..., name @ Until(...), ...) arms can find the parts of the input they
+// bind after the match is over, and Prefix(..., rest) arms can give back the
+// bytes read past the prefix
+#[cfg(feature = "std")]
+pub struct Recorded<I> {
+    iter: I,
+    buf: Vec<u8>,
+}
+
+#[cfg(feature = "std")]
+impl<E, I: Iterator<Item = Result<u8, E>>> Iterator for Recorded<I> {
+    type Item = Result<u8, E>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.iter.next();
+        if let Some(Ok(b)) = next {
+            self.buf.push(b);
+        }
+        next
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+// a Suffix(..., rest) reads the input from the back, so the bytes we record
+// are in the order we read them, which is backwards
+#[cfg(feature = "std")]
+impl<E, I: DoubleEndedIterator<Item = Result<u8, E>>> DoubleEndedIterator for Recorded<I> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let next = self.iter.next_back();
+        if let Some(Ok(b)) = next {
+            self.buf.push(b);
+        }
+        next
+    }
+}
+
+// the iterator to read the input with if an arm binds part of it in a Seq(...)
+// or the rest of it: slices and strs can just be sliced, but anything else has
+// to be recorded
+#[diagnostic::on_unimplemented(
+    message = "binding part of the input or the rest of it needs the `std` feature \
+               unless the input is a slice or str",
+    label = "this isn't a slice or str, so the bytes read from it have to be kept in a `Vec`"
+)]
+pub trait Record {
+    type Recorded;
+    fn record(self) -> Self::Recorded;
+}
+
+impl<'a> Record for SliceBytes<'a> {
+    type Recorded = Self;
+    #[inline(always)]
+    fn record(self) -> Self::Recorded {
+        self
+    }
+}
+
+impl<'a> Record for StrBytes<'a> {
+    type Recorded = Self;
+    #[inline(always)]
+    fn record(self) -> Self::Recorded {
+        self
+    }
+}
+
+#[cfg(feature = "std")]
+impl<I> Record for Bytes<I> {
+    type Recorded = Recorded<Self>;
+    #[inline(always)]
+    fn record(self) -> Self::Recorded {
+        Recorded {
+            iter: self,
+            buf: Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<I> Record for RefBytes<I> {
+    type Recorded = Recorded<Self>;
+    #[inline(always)]
+    fn record(self) -> Self::Recorded {
+        Recorded {
+            iter: self,
+            buf: Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<I> Record for TryBytes<I> {
+    type Recorded = Recorded<Self>;
+    #[inline(always)]
+    fn record(self) -> Self::Recorded {
+        Recorded {
+            iter: self,
+            buf: Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<I> Record for TryRefBytes<I> {
+    type Recorded = Recorded<Self>;
+    #[inline(always)]
+    fn record(self) -> Self::Recorded {
+        Recorded {
+            iter: self,
+            buf: Vec::new(),
+        }
+    }
+}
+
+// the input bound names in a Seq(...) are found in, as far as the iterator
+// has read it (or all of it, for a slice or str)
+pub trait Input {
+    fn input(&self) -> &[u8];
+}
+
+impl<'a> Input for SliceBytes<'a> {
+    #[inline(always)]
+    fn input(&self) -> &[u8] {
+        self.slice
+    }
+}
+
+impl<'a> Input for StrBytes<'a> {
+    #[inline(always)]
+    fn input(&self) -> &[u8] {
+        self.s.as_bytes()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<I> Input for Recorded<I> {
+    #[inline(always)]
+    fn input(&self) -> &[u8] {
+        &self.buf
+    }
+}
+
+// what a name bound in a Seq(...) gets: the bytes of the input from `start`
+// to `end`, as a subslice of a slice or str, or a copy of the recorded bytes.
+// like the rest of the input, a str can only give them as a str if they're
+// `ALIGNED` to char boundaries
+#[diagnostic::on_unimplemented(
+    message = "this part of the input could start or end in the middle of a UTF-8 character, \
+               so it can't be bound as a `&str`",
+    label = "this could split a character",
+    note = "match on `.as_bytes()` to bind it as a `&[u8]` instead"
+)]
+pub trait Capture<const ALIGNED: bool> {
+    type Captured<'b>
+    where
+        Self: 'b;
+    fn capture(&self, start: usize, end: usize) -> Self::Captured<'_>;
+}
+
+impl<'a, const ALIGNED: bool> Capture<ALIGNED> for SliceBytes<'a> {
+    type Captured<'b>
+        = &'a [u8]
+    where
+        Self: 'b;
+    #[inline(always)]
+    fn capture(&self, start: usize, end: usize) -> Self::Captured<'_> {
+        &self.slice[start..end]
+    }
+}
+
+impl<'a> Capture<true> for StrBytes<'a> {
+    type Captured<'b>
+        = &'a str
+    where
+        Self: 'b;
+    #[inline(always)]
+    fn capture(&self, start: usize, end: usize) -> Self::Captured<'_> {
+        &self.s[start..end]
+    }
+}
+
+#[cfg(feature = "std")]
+impl<I, const ALIGNED: bool> Capture<ALIGNED> for Recorded<I> {
+    type Captured<'b>
+        = Vec<u8>
+    where
+        Self: 'b;
+    #[inline(always)]
+    fn capture(&self, start: usize, end: usize) -> Self::Captured<'_> {
+        self.buf[start..end].to_vec()
+    }
+}
+
+// reads `word` from the front of the input (or the back, if `back` is set) if
+// that's what's there, and says whether it did. slices and strs can look at
+// the whole word at once, which comparing arrays turns into one wide load and
+// compare; other iterators never do, and read it a byte at a time instead
+pub trait EatWord {
+    fn eat_word<const N: usize>(&mut self, word: &[u8; N], back: bool) -> bool;
+}
+
+#[inline(always)]
+fn eat_word<'a, const N: usize>(
+    iter: &mut core::slice::Iter<'a, u8>,
+    word: &[u8; N],
+    back: bool,
+) -> bool {
+    let rest: &'a [u8] = iter.as_slice();
+    if back {
+        if rest.last_chunk::<N>() == Some(word) {
+            *iter = rest[..rest.len() - N].iter();
+            return true;
+        }
+    } else if rest.first_chunk::<N>() == Some(word) {
+        *iter = rest[N..].iter();
+        return true;
+    }
+    false
+}
+
+impl EatWord for SliceBytes<'_> {
+    #[inline(always)]
+    fn eat_word<const N: usize>(&mut self, word: &[u8; N], back: bool) -> bool {
+        eat_word(&mut self.iter, word, back)
+    }
+}
+
+impl EatWord for StrBytes<'_> {
+    #[inline(always)]
+    fn eat_word<const N: usize>(&mut self, word: &[u8; N], back: bool) -> bool {
+        eat_word(&mut self.iter, word, back)
+    }
+}
+
+impl<I> EatWord for Bytes<I> {
+    #[inline(always)]
+    fn eat_word<const N: usize>(&mut self, _: &[u8; N], _: bool) -> bool {
+        false
+    }
+}
+
+impl<I> EatWord for RefBytes<I> {
+    #[inline(always)]
+    fn eat_word<const N: usize>(&mut self, _: &[u8; N], _: bool) -> bool {
+        false
+    }
+}
+
+impl<I> EatWord for TryBytes<I> {
+    #[inline(always)]
+    fn eat_word<const N: usize>(&mut self, _: &[u8; N], _: bool) -> bool {
+        false
+    }
+}
+
+impl<I> EatWord for TryRefBytes<I> {
+    #[inline(always)]
+    fn eat_word<const N: usize>(&mut self, _: &[u8; N], _: bool) -> bool {
+        false
+    }
+}
+
+#[cfg(feature = "std")]
+impl<I> EatWord for Recorded<I> {
+    #[inline(always)]
+    fn eat_word<const N: usize>(&mut self, _: &[u8; N], _: bool) -> bool {
+        false
+    }
+}
+
+// how many bytes are left in the input, for strategy = "length". only the
+// iterators for slices, strs and Strings know this before reading them
+#[diagnostic::on_unimplemented(
+    message = "strategy = \"length\" needs to know the length of the input before reading it",
+    label = "this isn't a slice, str or String"
+)]
+pub trait KnownLen {
+    fn known_len(&self) -> usize;
+}
+
+impl KnownLen for SliceBytes<'_> {
+    #[inline(always)]
+    fn known_len(&self) -> usize {
+        self.iter.len()
+    }
+}
+
+impl KnownLen for StrBytes<'_> {
+    #[inline(always)]
+    fn known_len(&self) -> usize {
+        self.iter.len()
+    }
+}
+
+#[cfg(feature = "std")]
+impl KnownLen for Bytes<std::vec::IntoIter<u8>> {
+    #[inline(always)]
+    fn known_len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<I: KnownLen> KnownLen for Recorded<I> {
+    #[inline(always)]
+    fn known_len(&self) -> usize {
+        self.iter.known_len()
     }
 }
 
@@ -1,9 +1,26 @@
-#![feature(result_copied)] // TODO
-pub use lighter_derive::lighter;
+use core::fmt;
+
+#[cfg(feature = "serde")]
+pub use lighter_derive::LighterDeserialize;
+pub use lighter_derive::{lighter, rewrite, LighterParse};
 
 #[doc(hidden)]
 pub mod __internal;
 
+/// The error returned when parsing a `#[derive(LighterParse)]` enum from a
+/// string that none of its variants match.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ParseError;
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("no variant matches the input")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
 /*
 TODO: tests
 #[cfg(test)]
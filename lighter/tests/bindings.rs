@@ -0,0 +1,65 @@
+// `name @ pattern` binds the literal that matched, which is the same string
+// a plain match would bind
+
+mod common;
+
+use common::str_inputs;
+use lighter::lighter;
+
+fn token(s: &str) -> String {
+    lighter! {
+        match s {
+            kw @ ("if" | "else" | "while") => format!("keyword {}", kw),
+            op @ ("+" | "+=" | "-") if op.len() == 1 => format!("op {}", op),
+            op @ "+=" => format!("assign {}", op),
+            _ => "other".to_string(),
+        }
+    }
+}
+
+fn token_plain(s: &str) -> String {
+    match s {
+        kw @ ("if" | "else" | "while") => format!("keyword {}", kw),
+        op @ ("+" | "+=" | "-") if op.len() == 1 => format!("op {}", op),
+        op @ "+=" => format!("assign {}", op),
+        _ => "other".to_string(),
+    }
+}
+
+#[test]
+fn plain_match() {
+    for input in str_inputs(b"+=-if", &["if", "else", "while", "whiles", "+="]) {
+        assert_eq!(token(&input), token_plain(&input), "for input {:?}", input);
+    }
+}
+
+#[test]
+fn static_text() {
+    // the binding outlives the input, and is bytes if any literal is
+    fn kind(s: &[u8]) -> &'static [u8] {
+        lighter! {
+            match s {
+                kind @ ("GET" | b"POST" | IgnoreAsciiCase("put")) => kind,
+                _ => b"".as_slice(),
+            }
+        }
+    }
+
+    assert_eq!(kind(b"GET"), b"GET");
+    assert_eq!(kind(b"POST"), b"POST");
+    assert_eq!(kind(b"PuT"), b"put");
+    assert_eq!(kind(b"HEAD"), b"");
+
+    fn word(s: &str) -> &'static str {
+        lighter! {
+            match s.bytes() {
+                word @ (Prefix("ab") | "c") => word,
+                _ => "",
+            }
+        }
+    }
+
+    assert_eq!(word("abc"), "ab");
+    assert_eq!(word("c"), "c");
+    assert_eq!(word("cd"), "");
+}
@@ -0,0 +1,123 @@
+// the names a Seq(...) binds get the same parts of the input as the groups
+// of the equivalent regex
+
+mod common;
+
+use common::inputs;
+use lighter::lighter;
+use regex::bytes::Regex;
+
+type Parts = (u32, Vec<u8>, Vec<u8>);
+
+const SEQS: &[&str] = &[
+    r"key=([^;]*)();",
+    r"v([0-9]+)\.([0-9]+)",
+    r"t=([\x00-\xff]{3})()[\x00-\xff]*",
+    r"([0-9]+)() ",
+];
+
+// the arm and groups of the first of SEQS to match, anchored at the start
+// (and at the end, for all but the last one)
+fn expected(regexes: &[Regex], input: &[u8]) -> Parts {
+    for (i, regex) in regexes.iter().enumerate() {
+        if let Some(groups) = regex.captures(input) {
+            return (i as u32, groups[1].to_vec(), groups[2].to_vec());
+        }
+    }
+    (regexes.len() as u32, Vec::new(), Vec::new())
+}
+
+fn slice(s: &[u8]) -> Parts {
+    lighter! {
+        match s {
+            Seq("key=", value @ Until(b';'), ";") => (0, value.to_vec(), Vec::new()),
+            Seq("v", major @ Digits, ".", minor @ Digits) => (1, major.to_vec(), minor.to_vec()),
+            Seq("t=", t @ Take(3), Many(b'\x00'..=b'\xff')) => (2, t.to_vec(), Vec::new()),
+            Prefix(Seq(n @ Digits, " ")) => (3, n.to_vec(), Vec::new()),
+            _ => (4, Vec::new(), Vec::new()),
+        }
+    }
+}
+
+// binding parts of any other iterator needs std
+#[cfg(feature = "std")]
+fn iter(s: &[u8]) -> Parts {
+    lighter! {
+        match s.iter().copied() {
+            Seq("key=", value @ Until(b';'), ";") => (0, value, Vec::new()),
+            Seq("v", major @ Digits, ".", minor @ Digits) => (1, major, minor),
+            Seq("t=", t @ Take(3), Many(b'\x00'..=b'\xff')) => (2, t, Vec::new()),
+            Prefix(Seq(n @ Digits, " ")) => (3, n, Vec::new()),
+            _ => (4, Vec::new(), Vec::new()),
+        }
+    }
+}
+
+#[test]
+fn regex_groups() {
+    let regexes: Vec<_> = SEQS
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let end = if i + 1 == SEQS.len() { "" } else { "$" };
+            Regex::new(&format!("(?s-u)^(?:{}){}", p, end)).unwrap()
+        })
+        .collect();
+    let words = &[
+        "key=;",
+        "key=abc;",
+        "key=a;b;",
+        "v1.2",
+        "v10.0",
+        "v.1",
+        "t=abc",
+        "t=\u{e9}a",
+        "t=ab",
+        "12 x",
+        " 1",
+    ];
+    for input in inputs(b"k=;v1. t\xc3", words) {
+        let expected = expected(&regexes, &input);
+        assert_eq!(slice(&input), expected, "for input {:?}", input);
+        #[cfg(feature = "std")]
+        assert_eq!(iter(&input), expected, "iter for input {:?}", input);
+    }
+}
+
+// a str can bind parts that are sure to start and end on char boundaries,
+// even when they hold multi-byte characters
+fn string(s: &str) -> (u32, &str) {
+    lighter! {
+        match s {
+            Seq("t=", t @ Take(3), ";") => (0, t),
+            Seq("k=", value @ Until(';'), ";", Many(b'\x00'..=b'\xff')) => (1, value),
+            _ => (2, ""),
+        }
+    }
+}
+
+#[test]
+fn strs() {
+    let regexes = [
+        Regex::new(r"(?s-u)^t=([\x00-\xff]{3});$").unwrap(),
+        Regex::new(r"(?s-u)^k=([^;]*);[\x00-\xff]*$").unwrap(),
+    ];
+    let words = &[
+        "t=a\u{e9};",
+        "t=\u{20ac};",
+        "t=\u{1f525};",
+        "k=\u{e9}\u{1f525};x",
+        "k=;",
+    ];
+    for input in common::str_inputs(b"t=k;\xc3\xa9", words) {
+        let expected = regexes
+            .iter()
+            .enumerate()
+            .find_map(|(i, regex)| {
+                let group = regex.captures(input.as_bytes())?.get(1)?;
+                Some((i as u32, &input[group.range()]))
+            })
+            .unwrap_or((2, ""));
+        assert_eq!(string(&input), expected, "for input {:?}", input);
+    }
+}
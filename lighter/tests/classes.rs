@@ -0,0 +1,43 @@
+// byte ranges, Any(...) and slice patterns match one byte from a set at a
+// time, like the same patterns in a plain match on a byte slice
+
+mod common;
+
+use common::inputs;
+use lighter::lighter;
+
+fn token(s: &[u8]) -> u32 {
+    lighter! {
+        match s {
+            [b'0', b'x' | b'X', Any("0123456789abcdef")] => 0,
+            b'0'..=b'9' => 1,
+            [b'a'..=b'z', b'0'..=b'9'] => 2,
+            Any(" \t") | [Any(" \t"), Any(" \t")] => 3,
+            [b'a'..b'c', (..=b'!')] => 4,
+            [(b'\x80'..), 'z'] => 5,
+            _ => 6,
+        }
+    }
+}
+
+fn token_plain(s: &[u8]) -> u32 {
+    match s {
+        [b'0', b'x' | b'X', b'0'..=b'9' | b'a'..=b'f'] => 0,
+        [b'0'..=b'9'] => 1,
+        [b'a'..=b'z', b'0'..=b'9'] => 2,
+        [b' ' | b'\t'] | [b' ' | b'\t', b' ' | b'\t'] => 3,
+        [b'a'..b'c', (..=b'!')] => 4,
+        [(b'\x80'..), b'z'] => 5,
+        _ => 6,
+    }
+}
+
+#[test]
+fn plain_match() {
+    let words = &[
+        "0x", "0X", "0xf", "0Xa", "0xg", "a1", "z9", " \t", "b!", "c!", "\u{e9}z",
+    ];
+    for input in inputs(b"0xa9 \t!z\xff", words) {
+        assert_eq!(token(&input), token_plain(&input), "for input {:?}", input);
+    }
+}
@@ -0,0 +1,134 @@
+// Many(...), Many1(...), Opt(...) and Seq(...) pick the same arm as trying
+// the equivalent regexes in turn with the regex crate
+
+mod common;
+
+use common::inputs;
+use lighter::lighter;
+use regex::bytes::Regex;
+
+fn first(regexes: &[Regex], input: &[u8]) -> u32 {
+    regexes
+        .iter()
+        .position(|r| r.is_match(input))
+        .unwrap_or(regexes.len()) as u32
+}
+
+fn compile(patterns: &[&str], wrap: fn(&str) -> String) -> Vec<Regex> {
+    patterns
+        .iter()
+        .map(|p| Regex::new(&wrap(p)).unwrap())
+        .collect()
+}
+
+fn flag(s: &[u8]) -> u32 {
+    lighter! {
+        match s {
+            Seq("--", Opt("no-"), "color") => 0,
+            Seq("-", Many1(b'v')) => 1,
+            Many("ab" | b'c') => 2,
+            Seq(Opt(b'+' | b'-'), Many1(b'0'..=b'9'), Opt(Seq(".", Many(b'0'..=b'9')))) => 3,
+            Many1(Seq(Any("xy"), Opt("z"))) => 4,
+            _ => 5,
+        }
+    }
+}
+
+fn flag_iter(s: &[u8]) -> u32 {
+    lighter! {
+        match s.iter() {
+            Seq("--", Opt("no-"), "color") => 0,
+            Seq("-", Many1(b'v')) => 1,
+            Many("ab" | b'c') => 2,
+            Seq(Opt(b'+' | b'-'), Many1(b'0'..=b'9'), Opt(Seq(".", Many(b'0'..=b'9')))) => 3,
+            Many1(Seq(Any("xy"), Opt("z"))) => 4,
+            _ => 5,
+        }
+    }
+}
+
+const FLAGS: &[&str] = &[
+    r"--(no-)?color",
+    r"-v+",
+    r"(ab|c)*",
+    r"[+\-]?[0-9]+(\.[0-9]*)?",
+    r"([xy]z?)+",
+];
+
+#[test]
+fn whole() {
+    let words = &[
+        "--color",
+        "--no-color",
+        "--no-",
+        "-vvv",
+        "abcab",
+        "cc",
+        "-12.5",
+        "+3.",
+        "xzyyz",
+        "zx",
+    ];
+    let regexes = compile(FLAGS, |p| format!("(?-u)^(?:{})$", p));
+    for input in inputs(b"-vabc1.xz", words) {
+        let expected = first(&regexes, &input);
+        assert_eq!(flag(&input), expected, "for input {:?}", input);
+        assert_eq!(flag_iter(&input), expected, "iter for input {:?}", input);
+    }
+}
+
+fn indent(s: &[u8]) -> u32 {
+    lighter! {
+        match s {
+            Prefix(Seq(Many1(Any(" \t")), "-")) => 0,
+            Prefix(Many1("  ")) => 1,
+            _ => 2,
+        }
+    }
+}
+
+#[test]
+fn prefix() {
+    let regexes = compile(&[r"[ \t]+-", r"(  )+"], |p| format!("(?-u)^(?:{})", p));
+    for input in inputs(b" \t-x", &["    - x", "\t\t-", "   x"]) {
+        let expected = first(&regexes, &input);
+        assert_eq!(indent(&input), expected, "for input {:?}", input);
+    }
+}
+
+fn trailing(s: &[u8]) -> u32 {
+    lighter! {
+        match s {
+            Suffix(Seq(Opt("\r"), "\n")) => 0,
+            Suffix(Many1(Any(" \t"))) => 1,
+            _ => 2,
+        }
+    }
+}
+
+#[test]
+fn suffix() {
+    let regexes = compile(&[r"\r?\n", r"[ \t]+"], |p| format!("(?-u)(?:{})$", p));
+    for input in inputs(b"\r\n \tx", &["x\r\n", "x \t ", "\n\r"]) {
+        let expected = first(&regexes, &input);
+        assert_eq!(trailing(&input), expected, "for input {:?}", input);
+    }
+}
+
+fn shout(s: &[u8]) -> u32 {
+    lighter! {
+        match s {
+            IgnoreAsciiCase(Seq("ha", Many("ha"), Opt("!"))) => 0,
+            _ => 1,
+        }
+    }
+}
+
+#[test]
+fn ignore_case() {
+    let regexes = compile(&["ha(ha)*!?"], |p| format!("(?i-u)^(?:{})$", p));
+    for input in inputs(b"hHaA!", &["HAhaHa!", "ha!!", "hA"]) {
+        let expected = first(&regexes, &input);
+        assert_eq!(shout(&input), expected, "for input {:?}", input);
+    }
+}
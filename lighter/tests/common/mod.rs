@@ -0,0 +1,83 @@
+// helpers shared by the tests, which each get their own copy of this module
+
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+
+// every string of up to three bytes from `alphabet`, then each of `words`
+// alone and with a byte from `alphabet` before or after it
+pub fn inputs(alphabet: &[u8], words: &[&str]) -> Vec<Vec<u8>> {
+    let mut inputs = vec![Vec::new()];
+    let mut last = vec![Vec::new()];
+    for _ in 0..3 {
+        last = last
+            .iter()
+            .flat_map(|input: &Vec<u8>| {
+                alphabet.iter().map(move |&b| {
+                    let mut input = input.clone();
+                    input.push(b);
+                    input
+                })
+            })
+            .collect();
+        inputs.extend(last.iter().cloned());
+    }
+
+    for word in words {
+        let word = word.as_bytes();
+        inputs.push(word.to_vec());
+        for &b in alphabet {
+            inputs.push([word, &[b]].concat());
+            inputs.push([&[b], word].concat());
+        }
+    }
+    inputs
+}
+
+// the same inputs, leaving out the ones that aren't valid UTF-8
+pub fn str_inputs(alphabet: &[u8], words: &[&str]) -> Vec<String> {
+    inputs(alphabet, words)
+        .into_iter()
+        .filter_map(|input| String::from_utf8(input).ok())
+        .collect()
+}
+
+// a guard that records that it ran
+pub fn guard(log: &RefCell<Vec<u32>>, id: u32, pass: bool) -> bool {
+    log.borrow_mut().push(id);
+    pass
+}
+
+// a test that runs the same arms in a lighter! and in a plain match on a
+// &str, and compares what they return and which guards ran, in what order
+#[macro_export]
+macro_rules! same_as_match {
+    ($name:ident, $alphabet:expr, $words:expr, |$s:ident, $log:ident| { $($arms:tt)* }) => {
+        #[test]
+        fn $name() {
+            #[allow(unused_variables)]
+            fn lighter($s: &str, $log: &::std::cell::RefCell<Vec<u32>>) -> u32 {
+                ::lighter::lighter! {
+                    match $s {
+                        $($arms)*
+                    }
+                }
+            }
+
+            #[allow(unused_variables)]
+            fn plain($s: &str, $log: &::std::cell::RefCell<Vec<u32>>) -> u32 {
+                match $s {
+                    $($arms)*
+                }
+            }
+
+            for input in $crate::common::str_inputs($alphabet, $words) {
+                let run = |f: fn(&str, &::std::cell::RefCell<Vec<u32>>) -> u32| {
+                    let log = ::std::cell::RefCell::new(Vec::new());
+                    (f(&input, &log), log.into_inner())
+                };
+                assert_eq!(run(lighter), run(plain), "for input {:?}", input);
+            }
+        }
+    };
+}
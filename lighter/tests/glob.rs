@@ -0,0 +1,152 @@
+// Glob(...) picks the same arm as trying each glob in turn with a simple
+// backtracking matcher
+
+mod common;
+
+use common::inputs;
+use lighter::lighter;
+
+// whether `glob` matches all of `input` (or just its start, if `prefix`),
+// where `?` is one UTF-8 character if `chars` and one byte otherwise
+fn glob_match(glob: &[u8], input: &[u8], chars: bool, prefix: bool) -> bool {
+    match glob {
+        [] => prefix || input.is_empty(),
+        [b'*', rest @ ..] => {
+            (0..=input.len()).any(|i| glob_match(rest, &input[i..], chars, prefix))
+        }
+        [b'?', rest @ ..] => {
+            let len = if chars {
+                (1..=input.len().min(4)).find(|&n| core::str::from_utf8(&input[..n]).is_ok())
+            } else {
+                (!input.is_empty()).then_some(1)
+            };
+            len.is_some_and(|n| glob_match(rest, &input[n..], chars, prefix))
+        }
+        [b'\\', b, rest @ ..] | [b, rest @ ..] => {
+            input.first() == Some(b) && glob_match(rest, &input[1..], chars, prefix)
+        }
+    }
+}
+
+fn first(globs: &[(&str, bool)], input: &[u8], prefix: bool) -> u32 {
+    globs
+        .iter()
+        .position(|&(glob, chars)| glob_match(glob.as_bytes(), input, chars, prefix))
+        .unwrap_or(globs.len()) as u32
+}
+
+fn file(s: &[u8]) -> u32 {
+    lighter! {
+        match s {
+            Glob("*.rs") => 0,
+            Glob("?.?") => 1,
+            Glob(b"v?.?") => 2,
+            Glob(r"\*?\?") => 3,
+            Glob("a*b*a") => 4,
+            _ => 5,
+        }
+    }
+}
+
+fn file_iter(s: &[u8]) -> u32 {
+    lighter! {
+        match s.iter().copied() {
+            Glob("*.rs") => 0,
+            Glob("?.?") => 1,
+            Glob(b"v?.?") => 2,
+            Glob(r"\*?\?") => 3,
+            Glob("a*b*a") => 4,
+            _ => 5,
+        }
+    }
+}
+
+#[test]
+fn whole() {
+    let globs = &[
+        ("*.rs", true),
+        ("?.?", true),
+        ("v?.?", false),
+        (r"\*?\?", true),
+        ("a*b*a", true),
+    ];
+    let words = &[
+        "main.rs", ".rs", "é.x", "vé.1", "v1.2", "*é?", "*??", "abba", "aba", "ab",
+    ];
+    for input in inputs(b"ab.*?\xc3\xa9", words) {
+        let expected = first(globs, &input, false);
+        assert_eq!(file(&input), expected, "for input {:?}", input);
+        assert_eq!(file_iter(&input), expected, "iter for input {:?}", input);
+    }
+}
+
+fn path(s: &[u8]) -> u32 {
+    lighter! {
+        match s {
+            Prefix(Glob("/*/")) => 0,
+            Prefix(Glob("?:")) => 1,
+            _ => 2,
+        }
+    }
+}
+
+#[test]
+fn prefix() {
+    let globs = &[("/*/", true), ("?:", true)];
+    let words = &["/usr/bin", "//", "C:\\", "é:", "/a"];
+    for input in inputs(b"/a:\xc3\xa9", words) {
+        let expected = first(globs, &input, true);
+        assert_eq!(path(&input), expected, "for input {:?}", input);
+    }
+}
+
+fn backup(s: &str) -> u32 {
+    lighter! {
+        match s {
+            Suffix(Glob("~")) => 0,
+            Suffix(Glob(".*.sw?")) => 1,
+            _ => 2,
+        }
+    }
+}
+
+#[test]
+fn suffix() {
+    let words = &["a~", ".a.swp", "x/.b.swé", ".swp", "..swo"];
+    for input in inputs(b".sw~p", words) {
+        let Ok(input) = String::from_utf8(input) else {
+            continue;
+        };
+        // a suffix glob matches if it matches some end of the input
+        let bytes = input.as_bytes();
+        let ends = |glob: &str| {
+            (0..=bytes.len()).any(|i| glob_match(glob.as_bytes(), &bytes[i..], true, false))
+        };
+        let expected = if ends("~") {
+            0
+        } else if ends(".*.sw?") {
+            1
+        } else {
+            2
+        };
+        assert_eq!(backup(&input), expected, "for input {:?}", input);
+    }
+}
+
+fn shout(s: &[u8]) -> u32 {
+    lighter! {
+        #[lighter(case_insensitive)]
+        match s {
+            Glob("*.txt") => 0,
+            _ => 1,
+        }
+    }
+}
+
+#[test]
+fn case_insensitive() {
+    for input in inputs(b".tTxX", &["a.TXT", "a.tXt", ".txt"]) {
+        let expected = first(&[("*.txt", true)], &input.to_ascii_lowercase(), false);
+        assert_eq!(shout(&input), expected, "for input {:?}", input);
+    }
+}
@@ -0,0 +1,50 @@
+// guards run in the order a plain match runs them, and when one fails, the
+// next arm that matches gets a try
+
+mod common;
+
+use common::guard;
+
+same_as_match!(literals, b"ehlo", &["hello", "help", "he", "hel"], |s, log| {
+    "hello" if guard(log, 0, s.len() > 10) => 0,
+    "hello" | "he" if guard(log, 1, true) => 1,
+    "help" if guard(log, 2, false) => 2,
+    "help" => 3,
+    "hel" if guard(log, 3, false) => 4,
+    _ if guard(log, 4, s.len() == 2) => 5,
+    _ => 6,
+});
+
+same_as_match!(many_guards, b"ab", &["ab", "ba"], |s, log| {
+    "a" if guard(log, 0, false) => 0,
+    "a" if guard(log, 1, false) => 1,
+    "ab" | "ba" if guard(log, 2, s.starts_with('b')) => 2,
+    "a" | "ab" if guard(log, 3, true) => 3,
+    _ => 4,
+});
+
+same_as_match!(empty, b"ab", &[], |s, log| {
+    "" if guard(log, 0, false) => 0,
+    "" => 1,
+    _ if guard(log, 1, s.len() % 2 == 1) => 2,
+    _ => 3,
+});
+
+// a guard can break out of a loop around the lighter!, just like the body
+#[test]
+fn control_flow() {
+    let mut seen = Vec::new();
+    for s in ["a", "b", "stop", "c"] {
+        lighter::lighter! {
+            match s {
+                "stop" if {
+                    seen.push(0);
+                    true
+                } => break,
+                "b" => continue,
+                _ => seen.push(1),
+            }
+        }
+    }
+    assert_eq!(seen, [1, 0]);
+}
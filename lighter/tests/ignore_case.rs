@@ -0,0 +1,51 @@
+// IgnoreAsciiCase(...) matches ASCII letters in either case, the same as
+// `eq_ignore_ascii_case`, and leaves every other byte alone
+
+mod common;
+
+use common::inputs;
+use lighter::lighter;
+
+fn keyword(s: &[u8]) -> u32 {
+    lighter! {
+        match s {
+            IgnoreAsciiCase("select") => 0,
+            IgnoreAsciiCase("from" | "where") => 1,
+            Prefix(IgnoreAsciiCase("--")) => 2,
+            IgnoreAsciiCase(b"\xc3\x89t\xc3\xa9") => 3,
+            "Sel" => 4,
+            _ => 5,
+        }
+    }
+}
+
+fn keyword_chain(s: &[u8]) -> u32 {
+    if s.eq_ignore_ascii_case(b"select") {
+        0
+    } else if s.eq_ignore_ascii_case(b"from") || s.eq_ignore_ascii_case(b"where") {
+        1
+    } else if s.starts_with(b"--") {
+        2
+    } else if s.eq_ignore_ascii_case("Été".as_bytes()) {
+        3
+    } else if s == b"Sel" {
+        4
+    } else {
+        5
+    }
+}
+
+#[test]
+fn eq_ignore_ascii_case() {
+    let words = &[
+        "select", "SELECT", "SeLeCt", "From", "WHERE", "--x", "Été", "ÉTé", "été",
+    ];
+    for input in inputs(b"sSeE-", words) {
+        assert_eq!(
+            keyword(&input),
+            keyword_chain(&input),
+            "for input {:?}",
+            input
+        );
+    }
+}
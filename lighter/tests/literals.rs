@@ -0,0 +1,79 @@
+// string, byte string, C string, byte and char literals all match the bytes
+// they stand for, whatever the input is
+
+use lighter::lighter;
+use std::ffi::CStr;
+
+fn method(s: &[u8]) -> u32 {
+    lighter! {
+        match s {
+            "GET" => 0,
+            b"POST" => 1,
+            c"PUT" => 2,
+            b'x' => 3,
+            'é' => 4,
+            b"\xff\xfe" => 5,
+            Prefix(b"\x00") => 6,
+            _ => 7,
+        }
+    }
+}
+
+#[test]
+fn slices() {
+    let cases: &[(&[u8], u32)] = &[
+        (b"GET", 0),
+        (b"POST", 1),
+        (b"PUT\0", 2),
+        (b"PUT", 7),
+        (b"PUT\0\0", 7),
+        (b"x", 3),
+        ("é".as_bytes(), 4),
+        (b"\xc3", 7),
+        (b"\xff\xfe", 5),
+        (b"\xff", 7),
+        (b"\x00PUT", 6),
+        (b"", 7),
+        (b"GETS", 7),
+    ];
+    for &(input, expected) in cases {
+        assert_eq!(method(input), expected, "for input {:?}", input);
+    }
+}
+
+#[test]
+fn iterators() {
+    fn name(s: &CStr) -> Option<u32> {
+        lighter! {
+            match s.to_bytes_with_nul().iter() {
+                c"hi" => Some(0),
+                b"hey\0" => Some(1),
+                "hello\0" => Some(2),
+                _ => None,
+            }
+        }
+    }
+
+    assert_eq!(name(c"hi"), Some(0));
+    assert_eq!(name(c"hey"), Some(1));
+    assert_eq!(name(c"hello"), Some(2));
+    assert_eq!(name(c"h"), None);
+    assert_eq!(name(c""), None);
+
+    fn chars(s: &str) -> u32 {
+        lighter! {
+            match s.bytes() {
+                'a' => 0,
+                "ab" => 1,
+                '√' => 2,
+                _ => 3,
+            }
+        }
+    }
+
+    assert_eq!(chars("a"), 0);
+    assert_eq!(chars("ab"), 1);
+    assert_eq!(chars("√"), 2);
+    assert_eq!(chars("√a"), 3);
+    assert_eq!(chars(""), 3);
+}
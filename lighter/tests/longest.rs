@@ -0,0 +1,138 @@
+// with prefer = "longest", the arm that matched the most input wins, ties go
+// to the arm listed first, and if its guard fails the next longest gets a try
+
+mod common;
+
+use common::inputs;
+use lighter::lighter;
+
+enum Pattern {
+    Whole(&'static str),
+    Prefix(&'static str),
+}
+
+// an arm's pattern, and whether its guard passes for an input
+type Arm = (Pattern, fn(&[u8]) -> bool);
+
+// the arm a longest-match picks from `arms`, or the arm after them if none do
+fn oracle(arms: &[Arm], input: &[u8]) -> u32 {
+    let mut best: Option<(usize, usize)> = None;
+    for (i, (pattern, guard)) in arms.iter().enumerate() {
+        let len = match *pattern {
+            Pattern::Whole(s) => (input == s.as_bytes()).then_some(s.len()),
+            Pattern::Prefix(s) => input.starts_with(s.as_bytes()).then_some(s.len()),
+        };
+        if let Some(len) = len.filter(|_| guard(input)) {
+            if best.is_none_or(|(best_len, _)| len > best_len) {
+                best = Some((len, i));
+            }
+        }
+    }
+    best.map_or(arms.len(), |(_, i)| i) as u32
+}
+
+fn always(_: &[u8]) -> bool {
+    true
+}
+
+fn never(_: &[u8]) -> bool {
+    false
+}
+
+fn bang(s: &[u8]) -> u32 {
+    lighter! {
+        #[lighter(prefer = "longest")]
+        match s {
+            Prefix("!") => 0,
+            Prefix("!=") if false => 1,
+            _ => 2,
+        }
+    }
+}
+
+#[test]
+fn failed_guard() {
+    assert_eq!(bang(b"!="), 0);
+    assert_eq!(bang(b"!=="), 0);
+    assert_eq!(bang(b"!"), 0);
+    assert_eq!(bang(b"="), 2);
+
+    let arms: &[Arm] = &[
+        (Pattern::Prefix("!"), always),
+        (Pattern::Prefix("!="), never),
+    ];
+    for input in inputs(b"!=", &[]) {
+        assert_eq!(bang(&input), oracle(arms, &input), "for input {:?}", input);
+    }
+}
+
+fn even(s: &[u8]) -> bool {
+    s.len().is_multiple_of(2)
+}
+
+fn short(s: &[u8]) -> bool {
+    s.len() < 4
+}
+
+fn op(s: &[u8]) -> u32 {
+    lighter! {
+        #[lighter(prefer = "longest")]
+        match s {
+            Prefix("<") => 0,
+            Prefix("<<=") if s.len().is_multiple_of(2) => 1,
+            Prefix("<<") if s.len() < 4 => 2,
+            "<=" => 3,
+            Prefix("<=") if s.len().is_multiple_of(2) => 4,
+            Prefix("<<<") => 5,
+            _ => 6,
+        }
+    }
+}
+
+#[test]
+fn operators() {
+    let arms: &[Arm] = &[
+        (Pattern::Prefix("<"), always),
+        (Pattern::Prefix("<<="), even),
+        (Pattern::Prefix("<<"), short),
+        (Pattern::Whole("<="), always),
+        (Pattern::Prefix("<="), even),
+        (Pattern::Prefix("<<<"), always),
+    ];
+    for input in inputs(b"<=", &["<<<=", "<<=<", "<<<<<"]) {
+        assert_eq!(op(&input), oracle(arms, &input), "for input {:?}", input);
+    }
+
+    assert_eq!(op(b"<<="), 2);
+    assert_eq!(op(b"<<=x"), 1);
+    assert_eq!(op(b"<<=xy"), 0);
+    assert_eq!(op(b"<="), 3);
+    assert_eq!(op(b"<=x"), 0);
+    assert_eq!(op(b"<<<"), 5);
+    assert_eq!(op(b"x"), 6);
+}
+
+fn tie(s: &[u8]) -> u32 {
+    lighter! {
+        #[lighter(prefer = "longest")]
+        match s {
+            Prefix("a") => 0,
+            Prefix("ab") if s.len() > 3 => 1,
+            "ab" => 2,
+            Prefix("ab") => 3,
+            "abc" if s[0] == b'x' => 4,
+            _ if s.len() == 3 => 5,
+            _ => 6,
+        }
+    }
+}
+
+#[test]
+fn ties() {
+    assert_eq!(tie(b"ab"), 2);
+    assert_eq!(tie(b"abcd"), 1);
+    assert_eq!(tie(b"abc"), 3);
+    assert_eq!(tie(b"a"), 0);
+    assert_eq!(tie(b"bbb"), 5);
+    assert_eq!(tie(b""), 6);
+}
@@ -0,0 +1,73 @@
+// #[lighter(...)] on the match changes how every arm matches, or where the
+// generated code finds the lighter crate
+
+mod common;
+
+use common::inputs;
+use lighter::lighter;
+
+mod reexport {
+    pub use lighter as renamed;
+}
+
+fn keyword(s: &[u8]) -> u32 {
+    lighter! {
+        #[lighter(case_insensitive)]
+        match s {
+            "select" => 0,
+            b"from" | Prefix("--") => 1,
+            Regex("[a-z]+_id") => 2,
+            Glob("*.SQL") => 3,
+            _ => 4,
+        }
+    }
+}
+
+fn keyword_chain(s: &[u8]) -> u32 {
+    let lower = s.to_ascii_lowercase();
+    if lower == b"select" {
+        0
+    } else if lower == b"from" || lower.starts_with(b"--") {
+        1
+    } else if lower
+        .strip_suffix(b"_id")
+        .is_some_and(|name| !name.is_empty() && name.iter().all(u8::is_ascii_lowercase))
+    {
+        2
+    } else if lower.ends_with(b".sql") {
+        3
+    } else {
+        4
+    }
+}
+
+#[test]
+fn case_insensitive() {
+    let words = &[
+        "SELECT", "Select", "FROM", "--", "user_ID", "USER_id", "_id", "a.Sql",
+    ];
+    for input in inputs(b"sS-_.", words) {
+        assert_eq!(
+            keyword(&input),
+            keyword_chain(&input),
+            "for input {:?}",
+            input
+        );
+    }
+}
+
+#[test]
+fn krate() {
+    fn id(s: &str) -> u32 {
+        reexport::renamed::lighter! {
+            #[lighter(crate = crate::reexport::renamed)]
+            match s {
+                "a" => 0,
+                _ => 1,
+            }
+        }
+    }
+
+    assert_eq!(id("a"), 0);
+    assert_eq!(id("b"), 1);
+}
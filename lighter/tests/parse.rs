@@ -0,0 +1,86 @@
+// #[derive(LighterParse)] parses each variant from its name (or `rename`) and
+// any aliases, and nothing else
+
+use lighter::{LighterParse, ParseError};
+use std::str::FromStr;
+
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, LighterParse)]
+enum Keyword {
+    If,
+    Else,
+    #[lighter(rename = "fn", alias = "func", alias = "function")]
+    Function,
+    r#type,
+    #[lighter(rename = "")]
+    Empty,
+}
+
+#[test]
+fn names() {
+    assert_eq!("If".parse(), Ok(Keyword::If));
+    assert_eq!(Keyword::from_str("Else"), Ok(Keyword::Else));
+    assert_eq!("fn".parse(), Ok(Keyword::Function));
+    assert_eq!("func".parse(), Ok(Keyword::Function));
+    assert_eq!("function".parse(), Ok(Keyword::Function));
+    assert_eq!("type".parse(), Ok(Keyword::r#type));
+    assert_eq!(Keyword::r#type.as_str(), "type");
+    assert_eq!("".parse(), Ok(Keyword::Empty));
+}
+
+#[test]
+fn try_from() {
+    assert_eq!(Keyword::try_from("If"), Ok(Keyword::If));
+    assert_eq!(Keyword::try_from(b"func".as_slice()), Ok(Keyword::Function));
+    assert_eq!(Keyword::try_from(b"\xff".as_slice()), Err(ParseError));
+}
+
+#[test]
+fn no_match() {
+    for s in ["if", "IF", "Function", "r#type", "fn ", " fn", "Iff", "Els"] {
+        assert_eq!(s.parse::<Keyword>(), Err(ParseError), "for input {:?}", s);
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, LighterParse)]
+#[lighter(case_insensitive)]
+enum Method {
+    Get,
+    #[lighter(rename = "POST")]
+    Post,
+}
+
+#[test]
+fn case_insensitive() {
+    for s in ["get", "GET", "gEt"] {
+        assert_eq!(s.parse(), Ok(Method::Get), "for input {:?}", s);
+    }
+    for s in ["post", "POST", "Post"] {
+        assert_eq!(s.parse(), Ok(Method::Post), "for input {:?}", s);
+    }
+    assert_eq!("put".parse::<Method>(), Err(ParseError));
+}
+
+// every unit variant prints as the string it parses from first
+#[test]
+fn round_trip() {
+    let all = [
+        Keyword::If,
+        Keyword::Else,
+        Keyword::Function,
+        Keyword::r#type,
+        Keyword::Empty,
+    ];
+    assert_eq!(
+        Keyword::ALL.iter().map(|&(_, v)| v).collect::<Vec<_>>(),
+        all
+    );
+    for &(s, v) in Keyword::ALL {
+        assert_eq!(v.as_str(), s);
+        assert_eq!(v.to_string(), s);
+        assert_eq!(s.parse(), Ok(v));
+    }
+    assert_eq!(Keyword::Function.as_str(), "fn");
+    assert_eq!(format!("[{:>4}]", Keyword::If), "[  If]");
+    assert_eq!(Method::Post.to_string(), "POST");
+}
@@ -0,0 +1,122 @@
+// variants with fields parse the rest of the input after their prefix with
+// the fields' FromStr, and print it back with their Display
+
+use lighter::{LighterParse, ParseError};
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, LighterParse)]
+enum Cmd {
+    #[lighter(prefix = "set ")]
+    Set(String),
+    #[lighter(prefix = "move ", separator = ",")]
+    Move {
+        x: i32,
+        y: i32,
+    },
+    #[lighter(prefix = "say ")]
+    Say(u8, String),
+    Quit,
+}
+
+#[test]
+fn parse() {
+    assert_eq!("set x".parse(), Ok(Cmd::Set("x".into())));
+    assert_eq!("set ".parse(), Ok(Cmd::Set("".into())));
+    assert_eq!("set a b".parse(), Ok(Cmd::Set("a b".into())));
+    assert_eq!("move 1,-2".parse(), Ok(Cmd::Move { x: 1, y: -2 }));
+    assert_eq!("say 3 hi there".parse(), Ok(Cmd::Say(3, "hi there".into())));
+    assert_eq!("Quit".parse(), Ok(Cmd::Quit));
+}
+
+#[test]
+fn bad_fields() {
+    for s in [
+        "set",
+        "move 1",
+        "move 1,",
+        "move 1,2,3",
+        "move a,2",
+        "say x hi",
+        "say 3",
+        "Quit ",
+    ] {
+        assert_eq!(s.parse::<Cmd>(), Err(ParseError), "for input {:?}", s);
+    }
+    assert_eq!(Cmd::try_from(b"set \xff".as_slice()), Err(ParseError));
+}
+
+// if a variant's fields don't parse, a variant with a shorter prefix of the
+// input still gets a try, as it would in a hand-written match with guards
+#[derive(Debug, PartialEq, LighterParse)]
+enum Size {
+    #[lighter(prefix = "width=")]
+    Width(u32),
+    #[lighter(prefix = "w=")]
+    Short(u32),
+    #[lighter(prefix = "w")]
+    W(String),
+}
+
+#[test]
+fn shorter_prefix() {
+    assert_eq!("width=5".parse(), Ok(Size::Width(5)));
+    assert_eq!("width=x".parse(), Ok(Size::W("idth=x".into())));
+    assert_eq!("w=5".parse(), Ok(Size::Short(5)));
+    assert_eq!("w=x".parse(), Ok(Size::W("=x".into())));
+    assert_eq!("w".parse(), Ok(Size::W("".into())));
+    assert_eq!("x".parse::<Size>(), Err(ParseError));
+}
+
+#[test]
+fn round_trip() {
+    let cmds = [
+        Cmd::Set("a b".into()),
+        Cmd::Move { x: -1, y: 20 },
+        Cmd::Say(7, "hi there".into()),
+        Cmd::Quit,
+    ];
+    for cmd in cmds {
+        let s = cmd.to_string();
+        assert!(s.starts_with(cmd.as_str()), "{:?} printed as {:?}", cmd, s);
+        assert_eq!(Cmd::from_str(&s), Ok(cmd), "for input {:?}", s);
+    }
+    assert_eq!(Cmd::Move { x: 1, y: 2 }.to_string(), "move 1,2");
+    assert_eq!(Cmd::Say(1, "x".into()).as_str(), "say ");
+    assert_eq!(Cmd::ALL.len(), 1);
+}
+
+#[derive(Debug, PartialEq, LighterParse)]
+enum Gen<T: FromStr> {
+    #[lighter(prefix = "x ")]
+    X(T),
+    #[lighter(prefix = "pair ", separator = ":")]
+    Pair(T, u8),
+    Y,
+}
+
+#[derive(Debug, PartialEq)]
+struct Wrapped<T>(T);
+
+impl<T: FromStr> FromStr for Wrapped<T> {
+    type Err = T::Err;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Wrapped)
+    }
+}
+
+#[test]
+fn generics() {
+    assert_eq!("x 5".parse(), Ok(Gen::X(5u32)));
+    assert_eq!("x -5".parse::<Gen<u32>>(), Err(ParseError));
+    assert_eq!("x hi".parse(), Ok(Gen::X(String::from("hi"))));
+    assert_eq!("Y".parse(), Ok(Gen::<bool>::Y));
+    assert_eq!("pair ab:7".parse(), Ok(Gen::Pair(String::from("ab"), 7)));
+    assert_eq!(Gen::X(1.5).to_string(), "x 1.5");
+    assert_eq!(Gen::Pair('c', 2).to_string(), "pair c:2");
+    assert_eq!(Gen::<u8>::ALL.len(), 1);
+
+    // a type that only implements FromStr can be parsed but not printed
+    assert_eq!("x 3".parse(), Ok(Gen::X(Wrapped(3u8))));
+    assert_eq!("x 3".parse::<Gen<Wrapped<bool>>>(), Err(ParseError));
+}
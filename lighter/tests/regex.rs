@@ -0,0 +1,150 @@
+// Regex(...) is compiled into the trie at expansion, and picks the same arm as
+// trying each regex in turn with the regex crate, anchored at both ends (or
+// just the start or end inside Prefix(...) or Suffix(...))
+
+mod common;
+
+use common::inputs;
+use lighter::lighter;
+use regex::bytes::Regex;
+
+// the arm the first of `patterns` to match `input` would pick, or the arm
+// after the last one if none do
+fn first(regexes: &[Regex], input: &[u8]) -> u32 {
+    regexes
+        .iter()
+        .position(|r| r.is_match(input))
+        .unwrap_or(regexes.len()) as u32
+}
+
+fn compile(patterns: &[&str], wrap: fn(&str) -> String) -> Vec<Regex> {
+    patterns
+        .iter()
+        .map(|p| Regex::new(&wrap(p)).unwrap())
+        .collect()
+}
+
+const TOKENS: &[&str] = &[
+    r"(?i)select|from",
+    r"[a-z_][a-z0-9_]*",
+    r"[0-9]+(\.[0-9]*)?",
+    r#""([^"\\]|\\.)*""#,
+    r"\p{Greek}+",
+    r".",
+];
+
+fn token(s: &[u8]) -> u32 {
+    lighter! {
+        match s {
+            Regex(r"(?i)select|from") => 0,
+            Regex(r"[a-z_][a-z0-9_]*") => 1,
+            Regex(r"[0-9]+(\.[0-9]*)?") => 2,
+            Regex(r#""([^"\\]|\\.)*""#) => 3,
+            Regex(r"\p{Greek}+") => 4,
+            Regex(r".") => 5,
+            _ => 6,
+        }
+    }
+}
+
+fn token_iter(s: &[u8]) -> u32 {
+    lighter! {
+        match s.iter() {
+            Regex(r"(?i)select|from") => 0,
+            Regex(r"[a-z_][a-z0-9_]*") => 1,
+            Regex(r"[0-9]+(\.[0-9]*)?") => 2,
+            Regex(r#""([^"\\]|\\.)*""#) => 3,
+            Regex(r"\p{Greek}+") => 4,
+            Regex(r".") => 5,
+            _ => 6,
+        }
+    }
+}
+
+#[test]
+fn whole() {
+    let words = &[
+        "SeLeCt",
+        "from",
+        "x_1",
+        "3.14",
+        "10.",
+        r#""a\"b""#,
+        "αβγ",
+        "é",
+        "\u{1F525}",
+    ];
+    let regexes = compile(TOKENS, |p| format!("^(?:{})$", p));
+    for input in inputs(b"aS0._\"\\\xce\xb1\xff", words) {
+        let expected = first(&regexes, &input);
+        assert_eq!(token(&input), expected, "for input {:?}", input);
+        assert_eq!(token_iter(&input), expected, "iter for input {:?}", input);
+    }
+}
+
+fn scheme(s: &[u8]) -> u32 {
+    lighter! {
+        match s {
+            Prefix(Regex(r"https?://")) => 0,
+            Prefix(Regex(r"[a-z][a-z0-9+.\-]*:")) => 1,
+            Prefix(Regex(r"/+")) => 2,
+            _ => 3,
+        }
+    }
+}
+
+#[test]
+fn prefix() {
+    let words = &["https://x", "http://", "git+ssh:", "a:", "//x", "1a:"];
+    let regexes = compile(&[r"https?://", r"[a-z][a-z0-9+.\-]*:", r"/+"], |p| {
+        format!("^(?:{})", p)
+    });
+    for input in inputs(b"a1:/+", words) {
+        let expected = first(&regexes, &input);
+        assert_eq!(scheme(&input), expected, "for input {:?}", input);
+    }
+}
+
+fn version(s: &str) -> u32 {
+    lighter! {
+        match s {
+            Suffix(Regex(r"-v[0-9]+")) => 0,
+            Suffix(Regex(r"\.(tar\.)?gz")) => 1,
+            _ => 2,
+        }
+    }
+}
+
+#[test]
+fn suffix() {
+    let words = &["x-v12", "-v", "a.tar.gz", ".gz", "tar.gz-v1"];
+    let regexes = compile(&[r"-v[0-9]+", r"\.(tar\.)?gz"], |p| format!("(?:{})$", p));
+    for input in inputs(b"v1-.", words) {
+        let Ok(input) = String::from_utf8(input) else {
+            continue;
+        };
+        let expected = first(&regexes, input.as_bytes());
+        assert_eq!(version(&input), expected, "for input {:?}", input);
+    }
+}
+
+fn ident(s: &[u8]) -> u32 {
+    lighter! {
+        #[lighter(case_insensitive)]
+        match s {
+            Regex("[a-z]+[0-9]") => 0,
+            Regex("ß|k") => 1,
+            _ => 2,
+        }
+    }
+}
+
+#[test]
+fn case_insensitive() {
+    let words = &["abC1", "ẞ", "ß", "K", "\u{212A}"];
+    let regexes = compile(&["[a-z]+[0-9]", "ß|k"], |p| format!("(?i)^(?:{})$", p));
+    for input in inputs(b"aB1k", words) {
+        let expected = first(&regexes, &input);
+        assert_eq!(ident(&input), expected, "for input {:?}", input);
+    }
+}
@@ -0,0 +1,184 @@
+// Prefix(..., rest) and Suffix(..., rest) bind what's left of the input, which
+// for an iterator includes the bytes read past the prefix to rule out longer
+// patterns
+
+mod common;
+
+use common::inputs;
+use lighter::lighter;
+
+fn strip_chain(s: &[u8]) -> (u32, Vec<u8>) {
+    if s == b"ab" {
+        (0, Vec::new())
+    } else if s.starts_with(b"abcd") {
+        (1, Vec::new())
+    } else if let Some(rest) = s.strip_prefix(b"a") {
+        (2, rest.to_vec())
+    } else if let Some(rest) = s.strip_prefix(b"") {
+        (3, rest.to_vec())
+    } else {
+        unreachable!()
+    }
+}
+
+macro_rules! strip {
+    ($name:ident, $s:ident => $input:expr, |$rest:ident| $collect:expr $(, $unwrap:ident)?) => {
+        fn $name($s: &[u8]) -> (u32, Vec<u8>) {
+            (lighter! {
+                match $input {
+                    "ab" => (0, Vec::new()),
+                    Prefix("abcd") => (1, Vec::new()),
+                    Prefix("a", $rest) => (2, $collect),
+                    Prefix("", $rest) => (3, $collect),
+                }
+            })
+            $(.$unwrap())?
+        }
+    };
+}
+
+strip!(slice, s => s, |rest| rest.to_vec());
+
+// the rest of any other iterator needs std
+#[cfg(feature = "std")]
+strip!(bytes, s => s.iter().copied(), |rest| rest.collect());
+#[cfg(feature = "std")]
+strip!(refs, s => s.iter(), |rest| rest.copied().collect());
+#[cfg(feature = "std")]
+strip!(results, s => s.iter().copied().map(Ok::<u8, ()>), |rest| {
+    rest.collect::<Result<_, _>>().unwrap()
+}, unwrap);
+#[cfg(feature = "std")]
+strip!(ref_results, s => s.iter().map(Ok::<&u8, ()>), |rest| {
+    rest.map(|b| *b.unwrap()).collect()
+}, unwrap);
+#[cfg(feature = "std")]
+strip!(string, s => String::from_utf8_lossy(s).into_owned(), |rest| rest.collect());
+
+#[test]
+fn prefixes() {
+    for input in inputs(b"abcd", &["abcd", "abc"]) {
+        assert_eq!(
+            slice(&input),
+            strip_chain(&input),
+            "slice for input {:?}",
+            input
+        );
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn iter_prefixes() {
+    assert_eq!(bytes(b"acde"), (2, b"cde".to_vec()));
+    assert_eq!(bytes(b"abx"), (2, b"bx".to_vec()));
+    assert_eq!(bytes(b"abcx"), (2, b"bcx".to_vec()));
+    assert_eq!(bytes(b"abcdx"), (1, Vec::new()));
+
+    for input in inputs(b"abcd", &["abcd", "abc"]) {
+        let expected = strip_chain(&input);
+        assert_eq!(bytes(&input), expected, "bytes for input {:?}", input);
+        assert_eq!(refs(&input), expected, "refs for input {:?}", input);
+        assert_eq!(results(&input), expected, "results for input {:?}", input);
+        assert_eq!(
+            ref_results(&input),
+            expected,
+            "ref_results for input {:?}",
+            input
+        );
+        assert_eq!(string(&input), expected, "string for input {:?}", input);
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn suffixes() {
+    fn stem(s: &[u8]) -> (u32, Vec<u8>) {
+        lighter! {
+            match s.iter().copied() {
+                Suffix(".tar.gz", stem) => (0, stem.collect()),
+                Suffix(".gz", stem) => (1, stem.collect()),
+                _ => (2, Vec::new()),
+            }
+        }
+    }
+
+    fn stem_chain(s: &[u8]) -> (u32, Vec<u8>) {
+        if let Some(stem) = s.strip_suffix(b".tar.gz") {
+            (0, stem.to_vec())
+        } else if let Some(stem) = s.strip_suffix(b".gz") {
+            (1, stem.to_vec())
+        } else {
+            (2, Vec::new())
+        }
+    }
+
+    assert_eq!(stem(b"a.tar.gz"), (0, b"a".to_vec()));
+    assert_eq!(stem(b"a.ar.gz"), (1, b"a.ar".to_vec()));
+    for input in inputs(b"a.rgz", &[".tar.gz", "x.tar.gz", "r.gz", "ar.gz"]) {
+        assert_eq!(stem(&input), stem_chain(&input), "for input {:?}", input);
+    }
+
+    // the rest can be read from either end
+    fn ends(s: &[u8]) -> Option<(u8, u8)> {
+        lighter! {
+            match s.iter().copied() {
+                Suffix("xyz") => None,
+                Suffix("z", mut stem) => Some((stem.next()?, stem.next_back()?)),
+                _ => None,
+            }
+        }
+    }
+
+    assert_eq!(ends(b"abyz"), Some((b'a', b'y')));
+    assert_eq!(ends(b"ayz"), Some((b'a', b'y')));
+    assert_eq!(ends(b"ayyz"), Some((b'a', b'y')));
+    assert_eq!(ends(b"yz"), None);
+    assert_eq!(ends(b"xyz"), None);
+}
+
+#[test]
+fn strs() {
+    // the rest of a str is a str, as long as the pattern ends on a char
+    fn unit(s: &str) -> (u32, &str) {
+        lighter! {
+            match s {
+                Prefix("é", rest) => (0, rest),
+                Prefix(Seq(Digits, "x"), rest) => (1, rest),
+                Prefix("a" | Any("bc"), rest) => (2, rest),
+                _ => (3, s),
+            }
+        }
+    }
+
+    assert_eq!(unit("ét"), (0, "t"));
+    assert_eq!(unit("12xé"), (1, "é"));
+    assert_eq!(unit("ab"), (2, "b"));
+    assert_eq!(unit("cé"), (2, "é"));
+    assert_eq!(unit("à"), (3, "à"));
+
+    fn stem(s: &str) -> &str {
+        lighter! {
+            match s {
+                Suffix("ß" | "s", stem) => stem,
+                _ => s,
+            }
+        }
+    }
+
+    assert_eq!(stem("éß"), "é");
+    assert_eq!(stem("és"), "é");
+    assert_eq!(stem("é"), "é");
+
+    // a byte slice can split a char
+    fn split(s: &[u8]) -> &[u8] {
+        lighter! {
+            match s {
+                Prefix(b'\xc3', rest) => rest,
+                _ => s,
+            }
+        }
+    }
+
+    assert_eq!(split("é".as_bytes()), b"\xa9");
+}
@@ -0,0 +1,158 @@
+// #[lighter::rewrite] only rewrites the matches lighter! handles the same way,
+// so a rewritten fn returns what the same fn without it does
+
+mod common;
+
+use common::str_inputs;
+
+// the same items twice, once with #[lighter::rewrite] on each
+macro_rules! both {
+    ($($item:item)*) => {
+        mod rewritten {
+            $(#[lighter::rewrite] $item)*
+        }
+
+        mod plain {
+            $($item)*
+        }
+    };
+}
+
+both! {
+    pub fn keyword(s: &str) -> usize {
+        match s {
+            "if" | "else" => 0,
+            "while" => 1,
+            "" => 2,
+            _ => 3,
+        }
+    }
+
+    // an identifier arm binds the input, which lighter! can't do
+    pub fn len(s: &str) -> usize {
+        match s {
+            "if" => 0,
+            other => other.len(),
+        }
+    }
+
+    pub fn nested(s: &str) -> usize {
+        match s.len() {
+            0 => 0,
+            _ => match s {
+                "a" | "b" => 1,
+                x => x.len() + 1,
+            },
+        }
+    }
+
+    pub fn byte(s: &str) -> usize {
+        match s.as_bytes().first() {
+            Some(b'a') => 0,
+            Some(&b) => b as usize,
+            None => 1,
+        }
+    }
+
+    // an enum whose variants share names with lighter's patterns, but whose
+    // patterns have no strings in them
+    #[derive(Clone, Copy)]
+    pub enum Flag {
+        Opt(u8),
+        Many(u8),
+        Take(u32),
+    }
+
+    pub fn flag(flag: Flag) -> usize {
+        use Flag::*;
+        match flag {
+            Opt(b'-') => 0,
+            Many(b'0'..=b'9') => 1,
+            Take(3) => 2,
+            _ => 3,
+        }
+    }
+
+    pub struct Parser;
+
+    impl Parser {
+        pub fn greeting(&self, s: &str) -> usize {
+            match s {
+                "hi" => 0,
+                "hello" if s.len() == 5 => 1,
+                _ => 2,
+            }
+        }
+    }
+}
+
+#[test]
+fn same_as_plain() {
+    for input in str_inputs(b"abifw", &["if", "else", "while", "hi", "hello"]) {
+        let input = input.as_str();
+        assert_eq!(
+            rewritten::keyword(input),
+            plain::keyword(input),
+            "for input {:?}",
+            input
+        );
+        assert_eq!(
+            rewritten::len(input),
+            plain::len(input),
+            "for input {:?}",
+            input
+        );
+        assert_eq!(
+            rewritten::nested(input),
+            plain::nested(input),
+            "for input {:?}",
+            input
+        );
+        assert_eq!(
+            rewritten::byte(input),
+            plain::byte(input),
+            "for input {:?}",
+            input
+        );
+        assert_eq!(
+            rewritten::Parser.greeting(input),
+            plain::Parser.greeting(input),
+            "for input {:?}",
+            input
+        );
+    }
+}
+
+#[test]
+fn user_enum() {
+    macro_rules! check {
+        ($module:ident) => {{
+            use $module::Flag;
+
+            assert_eq!($module::flag(Flag::Opt(b'-')), 0);
+            assert_eq!($module::flag(Flag::Many(b'7')), 1);
+            assert_eq!($module::flag(Flag::Take(3)), 2);
+            assert_eq!($module::flag(Flag::Take(4)), 3);
+            assert_eq!($module::flag(Flag::Opt(b'+')), 3);
+        }};
+    }
+
+    check!(rewritten);
+    check!(plain);
+}
+
+#[lighter::rewrite]
+fn marked(s: &str) -> usize {
+    #[lighter]
+    match s.bytes() {
+        b'a' => 0,
+        _ => 1,
+    }
+}
+
+#[test]
+fn marked_byte_match() {
+    assert_eq!(marked("a"), 0);
+    assert_eq!(marked("ab"), 1);
+    assert_eq!(marked(""), 1);
+}
@@ -0,0 +1,227 @@
+// #[derive(LighterDeserialize)] identifies names the way serde_derive's own
+// field and variant identifiers do
+
+#![cfg(feature = "serde")]
+
+use lighter::{LighterDeserialize, LighterParse};
+use serde::de::{
+    self, Deserialize, Deserializer, IgnoredAny, IntoDeserializer, MapAccess, Visitor,
+};
+use std::fmt;
+use std::marker::PhantomData;
+
+#[derive(Debug, PartialEq, LighterParse, LighterDeserialize)]
+enum Field {
+    #[lighter(rename = "name")]
+    Name,
+    #[lighter(rename = "id", alias = "ID")]
+    Id,
+    #[lighter(rename = "tags")]
+    Tags,
+    #[lighter(other)]
+    Other,
+}
+
+// the same identifier, with the plain match serde_derive would generate
+#[derive(Debug, PartialEq)]
+enum PlainField {
+    Name,
+    Id,
+    Tags,
+    Other,
+}
+
+impl<'de> Deserialize<'de> for PlainField {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct PlainVisitor;
+
+        impl Visitor<'_> for PlainVisitor {
+            type Value = PlainField;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an identifier")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<PlainField, E> {
+                Ok(match v {
+                    "name" => PlainField::Name,
+                    "id" | "ID" => PlainField::Id,
+                    "tags" => PlainField::Tags,
+                    _ => PlainField::Other,
+                })
+            }
+        }
+
+        deserializer.deserialize_identifier(PlainVisitor)
+    }
+}
+
+// the fields of a struct, with whichever identifier `F` says they are
+trait Key {
+    fn field(&self) -> Option<u8>;
+}
+
+impl Key for Field {
+    fn field(&self) -> Option<u8> {
+        match self {
+            Field::Name => Some(0),
+            Field::Id => Some(1),
+            Field::Tags => Some(2),
+            Field::Other => None,
+        }
+    }
+}
+
+impl Key for PlainField {
+    fn field(&self) -> Option<u8> {
+        match self {
+            PlainField::Name => Some(0),
+            PlainField::Id => Some(1),
+            PlainField::Tags => Some(2),
+            PlainField::Other => None,
+        }
+    }
+}
+
+struct Person<F> {
+    name: Option<String>,
+    id: Option<u64>,
+    tags: Option<Vec<String>>,
+    ignored: usize,
+    key: PhantomData<F>,
+}
+
+impl<'de, F: Key + Deserialize<'de>> Deserialize<'de> for Person<F> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct PersonVisitor<F>(PhantomData<F>);
+
+        impl<'de, F: Key + Deserialize<'de>> Visitor<'de> for PersonVisitor<F> {
+            type Value = Person<F>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a person")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Person<F>, A::Error> {
+                let mut person = Person {
+                    name: None,
+                    id: None,
+                    tags: None,
+                    ignored: 0,
+                    key: PhantomData,
+                };
+                while let Some(key) = map.next_key::<F>()? {
+                    match key.field() {
+                        Some(0) => person.name = Some(map.next_value()?),
+                        Some(1) => person.id = Some(map.next_value()?),
+                        Some(2) => person.tags = Some(map.next_value()?),
+                        _ => {
+                            map.next_value::<IgnoredAny>()?;
+                            person.ignored += 1;
+                        }
+                    }
+                }
+                Ok(person)
+            }
+        }
+
+        deserializer.deserialize_map(PersonVisitor(PhantomData))
+    }
+}
+
+impl<F> Person<F> {
+    fn parts(self) -> (Option<String>, Option<u64>, Option<Vec<String>>, usize) {
+        (self.name, self.id, self.tags, self.ignored)
+    }
+}
+
+#[test]
+fn same_as_plain_match() {
+    let docs = [
+        r#"{}"#,
+        r#"{"name": "Ada", "id": 1}"#,
+        r#"{"ID": 2, "tags": ["a", "b"], "nam": 3, "names": 4}"#,
+        r#"{"Other": null, "other": {"name": "x"}, "": 5, "name": "escaped"}"#,
+        r#"{"Name": "wrong case", "id": 7, "tags": []}"#,
+    ];
+    for doc in docs {
+        let lighter: Person<Field> = serde_json::from_str(doc).unwrap();
+        let plain: Person<PlainField> = serde_json::from_str(doc).unwrap();
+        assert_eq!(lighter.parts(), plain.parts(), "for {}", doc);
+    }
+}
+
+#[test]
+fn round_trip() {
+    let value = serde_json::json!({"name": "Ada", "id": 36, "tags": ["x"], "extra": true});
+    let person: Person<Field> = serde_json::from_str(&value.to_string()).unwrap();
+    assert_eq!(
+        person.parts(),
+        (Some("Ada".into()), Some(36), Some(vec!["x".into()]), 1)
+    );
+
+    // the other variant isn't one of the names, or one of ALL
+    for (s, field) in Field::ALL {
+        let json = serde_json::to_string(s).unwrap();
+        assert_eq!(serde_json::from_str::<Field>(&json).unwrap(), *field);
+    }
+    assert_eq!(Field::ALL.len(), 3);
+    assert_eq!(
+        serde_json::from_str::<Field>(r#""Other""#).unwrap(),
+        Field::Other
+    );
+    assert_eq!(serde_json::from_str::<Field>(r#""ID""#).unwrap(), Field::Id);
+}
+
+#[derive(Debug, PartialEq, LighterParse, LighterDeserialize)]
+enum Color {
+    Red,
+    #[lighter(rename = "green", alias = "GREEN")]
+    Green,
+}
+
+#[test]
+fn unknown_variant() {
+    let err = serde_json::from_str::<Color>(r#""blue""#).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "unknown variant `blue`, expected `Red` or `green` at line 1 column 6"
+    );
+    assert_eq!(
+        serde_json::from_str::<Color>(r#""GREEN""#).unwrap(),
+        Color::Green
+    );
+}
+
+#[test]
+fn index() {
+    let color = |i: u64| Color::deserialize(i.into_deserializer());
+    assert_eq!(color(0), Ok::<_, de::value::Error>(Color::Red));
+    assert_eq!(color(1), Ok(Color::Green));
+    assert!(color(2).is_err());
+
+    // with an other variant, any index past the end goes to it
+    let field = |i: u64| Field::deserialize(i.into_deserializer());
+    assert_eq!(field(0), Ok::<_, de::value::Error>(Field::Name));
+    assert_eq!(field(2), Ok(Field::Tags));
+    assert_eq!(field(3), Ok(Field::Other));
+    assert_eq!(field(4), Ok(Field::Other));
+    assert_eq!(field(u64::MAX), Ok(Field::Other));
+}
+
+// a field that doesn't parse goes to the other variant too
+#[derive(Debug, PartialEq, LighterParse)]
+enum Setting {
+    #[lighter(prefix = "width=")]
+    Width(u32),
+    #[lighter(other)]
+    Unknown,
+}
+
+#[test]
+fn unparsed_fields() {
+    assert_eq!("width=3".parse(), Ok(Setting::Width(3)));
+    assert_eq!("width=x".parse(), Ok(Setting::Unknown));
+    assert_eq!("height=3".parse(), Ok(Setting::Unknown));
+    assert!(Setting::ALL.is_empty());
+}
@@ -0,0 +1,338 @@
+// the table and length strategies have to pick the same arm as the nested one
+// for every input, run the same guards in the same order, and leave the same
+// rest
+
+mod common;
+
+use common::{guard, inputs};
+use lighter::lighter;
+use std::cell::RefCell;
+
+// a test that runs the same arms with every strategy (and any other options
+// given) on every input, and compares what they return and which guards ran,
+// then checks what they return for some inputs against what it should be
+macro_rules! compare {
+    (
+        $name:ident,
+        [$($opt:tt)*],
+        $alphabet:expr,
+        $words:expr,
+        |$s:ident, $log:ident| { $($arms:tt)* },
+        [$(($input:expr, $expected:expr)),* $(,)?]
+    ) => {
+        #[test]
+        fn $name() {
+            #[allow(unused_variables)]
+            fn nested($s: &[u8], $log: &RefCell<Vec<u32>>) -> String {
+                lighter! {
+                    #[lighter($($opt)* strategy = "nested")]
+                    match $s {
+                        $($arms)*
+                    }
+                }
+            }
+
+            #[allow(unused_variables)]
+            fn table($s: &[u8], $log: &RefCell<Vec<u32>>) -> String {
+                lighter! {
+                    #[lighter($($opt)* strategy = "table")]
+                    match $s {
+                        $($arms)*
+                    }
+                }
+            }
+
+            #[allow(unused_variables)]
+            fn length($s: &[u8], $log: &RefCell<Vec<u32>>) -> String {
+                lighter! {
+                    #[lighter($($opt)* strategy = "length")]
+                    match $s {
+                        $($arms)*
+                    }
+                }
+            }
+
+            for input in inputs($alphabet, $words) {
+                let run = |f: fn(&[u8], &RefCell<Vec<u32>>) -> String| {
+                    let log = RefCell::new(Vec::new());
+                    (f(&input, &log), log.into_inner())
+                };
+                let input = String::from_utf8_lossy(&input);
+                assert_eq!(run(nested), run(table), "table for input {:?}", input);
+                assert_eq!(run(nested), run(length), "length for input {:?}", input);
+            }
+
+            $(
+                let log = RefCell::new(Vec::new());
+                let input: &str = $input;
+                assert_eq!(nested(input.as_bytes(), &log), $expected, "for input {:?}", input);
+            )*
+        }
+    };
+}
+
+fn show(rest: &[u8]) -> String {
+    String::from_utf8_lossy(rest).into_owned()
+}
+
+compare!(literals, [], b"ehlop", &["hello", "help", "he", "hell"], |s, log| {
+    "hello" => "hello".into(),
+    "help" | "hell" => "help or hell".into(),
+    "he" => "he".into(),
+    _ => "none".into(),
+}, [
+    ("hello", "hello"),
+    ("help", "help or hell"),
+    ("hell", "help or hell"),
+    ("he", "he"),
+    ("h", "none"),
+    ("helloo", "none"),
+    ("", "none"),
+]);
+
+compare!(exhaustive, [], b"ab", &["abab"], |s, log| {
+    "" => "empty".into(),
+    "a" | "b" => "a or b".into(),
+    Glob(b"?") => "one".into(),
+    "ab" => "ab".into(),
+    Glob(b"??") => "two".into(),
+    Glob(b"???*") => "more".into(),
+}, [
+    ("", "empty"),
+    ("a", "a or b"),
+    ("x", "one"),
+    ("ab", "ab"),
+    ("ba", "two"),
+    ("abc", "more"),
+]);
+
+compare!(prefixes, [], b"ehlop", &["hello", "help", "he", "hell"], |s, log| {
+    "hello" => "hello".into(),
+    Prefix("hel", rest) => format!("hel + {}", show(rest)),
+    Prefix("he", rest) => format!("he + {}", show(rest)),
+    "h" => "h".into(),
+    _ => "none".into(),
+}, [
+    ("hello", "hello"),
+    ("hellox", "hel + lox"),
+    ("help", "hel + p"),
+    ("hel", "hel + "),
+    ("he", "he + "),
+    ("hex", "he + x"),
+    ("h", "h"),
+    ("x", "none"),
+]);
+
+compare!(suffixes, [], b".rsx", &["a.rs", ".rs", "x.rx"], |s, log| {
+    Suffix(".rs", stem) => format!("{}.rs", show(stem)),
+    Suffix("s") => "s".into(),
+    "x" => "x".into(),
+    _ => "none".into(),
+}, [
+    ("a.rs", "a.rs"),
+    (".rs", ".rs"),
+    ("xs", "s"),
+    ("s", "s"),
+    ("x", "x"),
+    ("rx", "none"),
+]);
+
+compare!(guards, [], b"abc", &["abc", "abcabc"], |s, log| {
+    Prefix("a", rest) if guard(log, 0, rest.len() % 2 == 0) => format!("a + {}", show(rest)),
+    "abc" if guard(log, 1, false) => "never".into(),
+    Prefix("ab") if guard(log, 2, s.len() > 3) => "ab".into(),
+    "abc" | "b" => "abc or b".into(),
+    _ if guard(log, 3, s.len() == 2) => "two".into(),
+    _ => "none".into(),
+}, [
+    ("abc", "a + bc"),
+    ("a", "a + "),
+    ("ab", "two"),
+    ("abcabc", "ab"),
+    ("b", "abc or b"),
+    ("c", "none"),
+]);
+
+compare!(regex, [], b"az09_-", &["abc123", "_x", "9"], |s, log| {
+    Regex("[a-z_][a-z0-9_]*") => "ident".into(),
+    Regex("[0-9]+") => "number".into(),
+    Glob("?*-") => "dashed".into(),
+    Many1("-") => "dashes".into(),
+    _ => "none".into(),
+}, [
+    ("abc123", "ident"),
+    ("_x", "ident"),
+    ("9", "number"),
+    ("a-", "dashed"),
+    ("--", "dashed"),
+    ("-", "dashes"),
+    ("", "none"),
+]);
+
+compare!(captures, [], b"k=v;0", &["key=value;", "k=;", "k=v;rest"], |s, log| {
+    Seq("k=", value @ Until(b';'), ";") => format!("value {}", show(value)),
+    Seq("key=", Many(Until(b';')), ";") => "key".into(),
+    Seq(n @ Digits, "=") => format!("number {}", show(n)),
+    _ => "none".into(),
+}, [
+    ("k=v;", "value v"),
+    ("k=;", "value "),
+    ("key=value;", "key"),
+    ("12=", "number 12"),
+    ("k=v;rest", "none"),
+]);
+
+compare!(ignore_case, [case_insensitive,], b"aAbB", &["abba", "ABBA", "Ab"], |s, log| {
+    "abba" => "abba".into(),
+    Prefix("ab", rest) => format!("ab + {}", show(rest)),
+    _ => "none".into(),
+}, [
+    ("abba", "abba"),
+    ("ABBA", "abba"),
+    ("Ab", "ab + "),
+    ("ABb", "ab + b"),
+    ("ba", "none"),
+]);
+
+compare!(longest, [prefer = "longest",], b"=!<", &["==", "!=", "<=", "<<="], |s, log| {
+    Prefix("=", rest) => format!("= + {}", show(rest)),
+    Prefix("==", rest) => format!("== + {}", show(rest)),
+    Prefix("!=") if guard(log, 0, s.len() > 2) => "!= and more".into(),
+    Prefix("!") => "!".into(),
+    Prefix("<<=") | Prefix("<=") => "shift or le".into(),
+    _ => "none".into(),
+}, [
+    ("=", "= + "),
+    ("==", "== + "),
+    ("===", "== + ="),
+    ("!=", "!"),
+    ("!=x", "!= and more"),
+    ("!", "!"),
+    ("<=", "shift or le"),
+    ("<<=", "shift or le"),
+    ("<<", "none"),
+]);
+
+// long runs of single bytes that the nested matches compare a word at a time,
+// including inputs that end or differ partway through a word
+compare!(
+    long_literals,
+    [],
+    b"ab",
+    &[
+        "abababababababababababababababababababab",
+        "abababababababababababababababababababa",
+        "ababababababababababababababababababaa",
+        "abababababababababababababababababababababababababababababababababababab",
+        "abababababababababababababababababababababababababababababababbbababab",
+        "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+        "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+    ],
+    |s, log| {
+        "abababababababababababababababababababab" => "40".into(),
+        "abababababababababababababababababababababababababababababababababababab" => "72".into(),
+        Prefix("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa", rest) => {
+            format!("a + {}", show(rest))
+        }
+        "ab" if guard(log, 0, true) => "ab".into(),
+        _ => "none".into(),
+    },
+    [
+        ("abababababababababababababababababababab", "40"),
+        ("abababababababababababababababababababababababababababababababababababab", "72"),
+        ("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa", "a + "),
+        ("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa", "a + aa"),
+        ("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa", "none"),
+        ("ab", "ab"),
+        ("abab", "none"),
+    ]
+);
+
+compare!(
+    long_suffixes,
+    [],
+    b"ab",
+    &["bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbab", "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbab"],
+    |s, log| {
+        Suffix("bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbab", stem) => format!("{} + b", show(stem)),
+        Suffix("ab") if guard(log, 0, s.len().is_multiple_of(2)) => "ab".into(),
+        _ => "none".into(),
+    },
+    [
+        ("bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbab", " + b"),
+        ("xbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbab", "x + b"),
+        ("bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbab", "none"),
+        ("ab", "ab"),
+        ("b", "none"),
+    ]
+);
+
+// the table reads from an iterator just as far as the nested matches do
+#[cfg(feature = "std")]
+#[test]
+fn iterator_rest() {
+    fn nested(s: &[u8]) -> (u32, Vec<u8>) {
+        lighter! {
+            #[lighter(strategy = "nested")]
+            match s.iter().copied() {
+                Prefix("ab", rest) => (0, rest.collect()),
+                Prefix("a", rest) => (1, rest.collect()),
+                _ => (2, Vec::new()),
+            }
+        }
+    }
+
+    fn table(s: &[u8]) -> (u32, Vec<u8>) {
+        lighter! {
+            #[lighter(strategy = "table")]
+            match s.iter().copied() {
+                Prefix("ab", rest) => (0, rest.collect()),
+                Prefix("a", rest) => (1, rest.collect()),
+                _ => (2, Vec::new()),
+            }
+        }
+    }
+
+    for input in inputs(b"abc", &[]) {
+        let expected = match (input.strip_prefix(b"ab"), input.strip_prefix(b"a")) {
+            (Some(rest), _) => (0, rest.to_vec()),
+            (None, Some(rest)) => (1, rest.to_vec()),
+            (None, None) => (2, Vec::new()),
+        };
+        assert_eq!(nested(&input), expected, "for input {:?}", input);
+        assert_eq!(table(&input), expected, "table for input {:?}", input);
+    }
+}
+
+// an iterator can't compare a word at once, so it reads one byte at a time
+// and still ends up in the same arm as a slice
+#[test]
+fn iterator_words() {
+    fn slice(s: &[u8]) -> u32 {
+        lighter! {
+            match s {
+                "abababababababababababababababababababab" => 0,
+                Prefix("abababababababababab") => 1,
+                "ab" => 2,
+                _ => 3,
+            }
+        }
+    }
+
+    fn iter(s: &[u8]) -> u32 {
+        lighter! {
+            match s.iter().copied() {
+                "abababababababababababababababababababab" => 0,
+                Prefix("abababababababababab") => 1,
+                "ab" => 2,
+                _ => 3,
+            }
+        }
+    }
+
+    let long = "abababababababababababababababababababab";
+    let words: Vec<&str> = (0..=long.len()).map(|i| &long[..i]).collect();
+    for input in inputs(b"ab", &words) {
+        assert_eq!(slice(&input), iter(&input), "for input {:?}", input);
+    }
+}
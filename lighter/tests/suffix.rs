@@ -0,0 +1,61 @@
+// Suffix(...) matches the end of the input, reading it backwards, and picks
+// the same arm an `ends_with` chain would
+
+mod common;
+
+use common::str_inputs;
+use lighter::lighter;
+
+fn ext(s: &str) -> u32 {
+    lighter! {
+        match s {
+            Suffix(".rs") => 0,
+            Suffix(".tar.gz") => 1,
+            Suffix(".gz") => 2,
+            "README" | Suffix("/README") => 3,
+            _ => 4,
+        }
+    }
+}
+
+fn ext_iter(s: &str) -> u32 {
+    lighter! {
+        match s.bytes() {
+            Suffix(".rs") => 0,
+            Suffix(".tar.gz") => 1,
+            Suffix(".gz") => 2,
+            "README" | Suffix("/README") => 3,
+            _ => 4,
+        }
+    }
+}
+
+fn ext_chain(s: &str) -> u32 {
+    if s.ends_with(".rs") {
+        0
+    } else if s.ends_with(".tar.gz") {
+        1
+    } else if s.ends_with(".gz") {
+        2
+    } else if s == "README" || s.ends_with("/README") {
+        3
+    } else {
+        4
+    }
+}
+
+#[test]
+fn ends_with() {
+    let words = &[
+        "a.rs",
+        "x.tar.gz",
+        ".gz",
+        "README",
+        "docs/README",
+        "READMEs",
+    ];
+    for input in str_inputs(b"./rsgz", words) {
+        assert_eq!(ext(&input), ext_chain(&input), "for input {:?}", input);
+        assert_eq!(ext_iter(&input), ext_chain(&input), "for input {:?}", input);
+    }
+}
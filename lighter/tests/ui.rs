@@ -0,0 +1,8 @@
+// the errors and warnings lighter! gives, pinned down with the spans and
+// messages rustc prints for them
+
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
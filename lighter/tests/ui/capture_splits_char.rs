@@ -0,0 +1,26 @@
+use lighter::lighter;
+
+fn main() {
+    let s = "t=aa\u{e9}";
+
+    lighter! {
+        match s {
+            Seq("t=", t @ Take(3), Many(b'\x00'..=b'\xff')) => (),
+            _ => (),
+        }
+    }
+
+    lighter! {
+        match s {
+            Seq(b'\xc3', t @ Until(b';'), ";") => (),
+            _ => (),
+        }
+    }
+
+    lighter! {
+        match s {
+            Seq("t=", t @ Take(4294967296)) => (),
+            _ => (),
+        }
+    }
+}
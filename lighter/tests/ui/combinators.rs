@@ -0,0 +1,15 @@
+use lighter::lighter;
+
+fn main() {
+    let s = "hello";
+
+    lighter! {
+        match s {
+            Seq() => (),
+            Opt("a", "b") => (),
+            Many() => (),
+            Many1(n @ Digits) => (),
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,13 @@
+use lighter::lighter;
+
+fn main() {
+    let s = "hello";
+
+    lighter! {
+        match s {
+            Glob("*\\") => (),
+            Glob('*') => (),
+            _ => (),
+        }
+    }
+}
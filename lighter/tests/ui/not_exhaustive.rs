@@ -0,0 +1,20 @@
+use lighter::lighter;
+
+fn main() {
+    let s = "hello";
+
+    lighter! {
+        match s {
+            "hi" => (),
+            Prefix("h") => (),
+        }
+    }
+
+    lighter! {
+        match s.as_bytes() {
+            Prefix(b'\x00'..=b'\x7f') => (),
+            "" => (),
+            _ if s.len() > 1 => (),
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use lighter::lighter;
+
+fn main() {
+    let s = "hello";
+
+    lighter! {
+        #[inline]
+        match s {
+            _ => (),
+        }
+    }
+
+    lighter! {
+        #[lighter(unknown)]
+        match s {
+            _ => (),
+        }
+    }
+
+    lighter! {
+        #[lighter(strategy = "fast")]
+        match s {
+            _ => (),
+        }
+    }
+
+    lighter! {
+        #[lighter(prefer = "shortest")]
+        match s {
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use lighter::LighterParse;
+
+#[derive(LighterParse)]
+enum Renamed {
+    Get,
+    #[lighter(rename = "Get")]
+    Fetch,
+}
+
+#[derive(LighterParse)]
+enum Aliased {
+    #[lighter(alias = "rm")]
+    Remove,
+    #[lighter(alias = "rm")]
+    Delete,
+}
+
+#[derive(LighterParse)]
+#[lighter(case_insensitive)]
+enum Folded {
+    Get,
+    #[lighter(rename = "GET")]
+    Fetch,
+}
+
+fn main() {}
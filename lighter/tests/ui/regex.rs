@@ -0,0 +1,14 @@
+use lighter::lighter;
+
+fn main() {
+    let s = "hello";
+
+    lighter! {
+        match s {
+            Regex("^hello$") => (),
+            Regex("[a-") => (),
+            Regex(1) => (),
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use lighter::lighter;
+
+fn main() {
+    let s = "é";
+
+    // the rest of a str can't start partway through a char
+    let _ = lighter! {
+        match s {
+            Prefix("a" | b'\xc3', rest) => rest.len(),
+            Prefix(Take(1), rest) => rest.len(),
+            _ => 0,
+        }
+    };
+
+    let _ = lighter! {
+        match s {
+            Suffix(b'\xa9', stem) => stem.len(),
+            _ => 0,
+        }
+    };
+
+    // but that's fine for a byte slice
+    let _ = lighter! {
+        match s.as_bytes() {
+            Prefix(b'\xc3', rest) => rest.len(),
+            _ => 0,
+        }
+    };
+}
@@ -0,0 +1,18 @@
+#![deny(deprecated)]
+
+use lighter::lighter;
+
+fn main() {
+    let s = "hello";
+
+    lighter! {
+        match s {
+            "hi" => (),
+            "hi" => (),
+            Prefix("he") => (),
+            "hello" => (),
+            _ => (),
+            "bye" => (),
+        }
+    }
+}
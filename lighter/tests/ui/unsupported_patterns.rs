@@ -0,0 +1,17 @@
+use lighter::lighter;
+
+fn main() {
+    let s = "hello";
+
+    // every arm lighter! can't handle is reported at once
+    lighter! {
+        match s {
+            1 => (),
+            1.5 => (),
+            Some(x) => (),
+            "a"..="z" => (),
+            ref x => (),
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+// `_` and identifier arms run where they are in the match, like in a plain
+// match: the arms before them come first, and the ones after them only run
+// if they have a guard that fails
+
+mod common;
+
+use common::guard;
+use lighter::lighter;
+
+same_as_match!(guarded, b"abc", &["abc", "abcd", "ab"], |s, log| {
+    "a" => 0,
+    _ if guard(log, 0, s.len() > 2) => 1,
+    "abc" | "abcd" => 2,
+    other if guard(log, 1, s.len() == 2) => 3,
+    "ab" | "b" => 4,
+    _ => 5,
+});
+
+same_as_match!(first, b"ab", &["ab"], |s, log| {
+    _ if guard(log, 0, s.is_empty()) => 0,
+    "a" if guard(log, 1, false) => 1,
+    "a" | "ab" => 2,
+    _ => 3,
+});
+
+#[test]
+#[allow(deprecated, unreachable_patterns)]
+fn unreachable() {
+    fn after_wild(s: &str) -> u32 {
+        lighter! {
+            match s {
+                "a" => 0,
+                _ => 1,
+                "b" => 2,
+            }
+        }
+    }
+
+    assert_eq!(after_wild("a"), 0);
+    assert_eq!(after_wild("b"), 1);
+    assert_eq!(after_wild(""), 1);
+}
+
+#[test]
+fn identifiers() {
+    // an identifier binds the bytes read before no arm could match
+    fn read(s: &str) -> Vec<u8> {
+        lighter! {
+            match s {
+                "hello" => Vec::new(),
+                "hey" => Vec::new(),
+                other => other.to_vec(),
+            }
+        }
+    }
+
+    assert_eq!(read("hxyz"), b"hx");
+    assert_eq!(read("hello!"), b"hello!");
+    assert_eq!(read("he"), b"he");
+    assert_eq!(read(""), b"");
+    assert_eq!(read("abc"), b"a");
+}